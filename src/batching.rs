@@ -0,0 +1,129 @@
+use crate::round::Round;
+use chrono::DateTime;
+use std::str::FromStr;
+
+// Seconds per unit of a time window
+static TIME_UNITS: &[(char, i64)] = &[('s', 1), ('m', 60), ('h', 3600), ('d', 86400)];
+
+/// How the rounds of the log are grouped into batches
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Batching {
+    // `rounds-per-batch` consecutive rounds
+    #[default]
+    Rounds,
+    // Rounds starting within the same wall-clock window of this many seconds
+    Time(i64),
+    // Rounds working on a ledger within the same range of this many sequences
+    Ledgers(u64),
+}
+
+impl FromStr for Batching {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Batching, String> {
+        let s = s.trim();
+        if s == "rounds" {
+            return Ok(Batching::Rounds);
+        }
+        if let Some(width) = s.strip_prefix("time:") {
+            let (amount, unit) = match TIME_UNITS.iter().find(|(unit, _)| width.ends_with(*unit)) {
+                Some((_, seconds)) => (&width[..width.len() - 1], *seconds),
+                None => (width, 1),
+            };
+            return match amount.parse::<i64>() {
+                Ok(amount) if amount > 0 => Ok(Batching::Time(amount * unit)),
+                _ => Err(format!(
+                    "invalid window \"{}\", expected a positive amount of s, m, h or d",
+                    width
+                )),
+            };
+        }
+        if let Some(width) = s.strip_prefix("ledgers:") {
+            return match width.parse::<u64>() {
+                Ok(width) if width > 0 => Ok(Batching::Ledgers(width)),
+                _ => Err(format!(
+                    "invalid window \"{}\", expected a positive amount of ledgers",
+                    width
+                )),
+            };
+        }
+
+        Err(format!(
+            "unknown batching \"{}\", expected rounds, time:<window> or ledgers:<window>",
+            s
+        ))
+    }
+}
+
+impl Batching {
+    /// Window the round falls in, None when batching by rounds or when the round has no timestamp
+    /// or ledger to place it by
+    pub fn window(&self, round: &Round) -> Option<i64> {
+        match self {
+            Batching::Rounds => None,
+            Batching::Time(width) => round
+                .start
+                .map(|start| start.and_utc().timestamp().div_euclid(*width)),
+            Batching::Ledgers(width) => round.ledger.map(|ledger| (ledger / width) as i64),
+        }
+    }
+
+    /// Amount of rounds at the front that form a finished batch, once the last round is complete
+    pub fn batch_end(&self, rounds: &[Round], rounds_per_batch: i32) -> Option<usize> {
+        let (last, previous) = rounds.split_last()?;
+        match self {
+            Batching::Rounds if rounds.len() as i32 == rounds_per_batch => Some(rounds.len()),
+            Batching::Rounds => None,
+            // Rounds that could not be placed stay with the batch they are logged in
+            _ => match (self.window(last), self.batch_window(previous)) {
+                (Some(window), Some(batch)) if window != batch => Some(previous.len()),
+                _ => None,
+            },
+        }
+    }
+
+    fn batch_window(&self, rounds: &[Round]) -> Option<i64> {
+        rounds.iter().find_map(|item| self.window(item))
+    }
+
+    /// Suffix for the output files of a batch, the window it covers or its first and last round
+    /// (e.g.: 2020-03-05T14:00_14:10, ledgers_5300000_5300499 or rounds_000_019)
+    pub fn name(&self, rounds: &[Round]) -> String {
+        let first = rounds[0].index;
+        let last = rounds[rounds.len() - 1].index;
+        let rounds_name = format!("rounds_{:03}_{:03}", first, last);
+        let window = match self.batch_window(rounds) {
+            Some(window) => window,
+            None => return rounds_name,
+        };
+
+        match self {
+            Batching::Rounds => rounds_name,
+            Batching::Time(width) => {
+                let start = DateTime::from_timestamp(window * width, 0);
+                let end = DateTime::from_timestamp((window + 1) * width, 0);
+                let (start, end) = match (start, end) {
+                    (Some(start), Some(end)) => (start.naive_utc(), end.naive_utc()),
+                    _ => return rounds_name,
+                };
+                // Seconds only when the window does not fall on whole minutes, the end date only
+                // when windows span days
+                let time = if width % 60 == 0 { "%H:%M" } else { "%H:%M:%S" };
+                let end_format = if *width >= 86400 {
+                    format!("%Y-%m-%dT{}", time)
+                } else {
+                    String::from(time)
+                };
+                format!(
+                    "{}_{}",
+                    start.format(&format!("%Y-%m-%dT{}", time)),
+                    end.format(&end_format)
+                )
+            }
+            Batching::Ledgers(width) => {
+                let start = window as u64 * width;
+                format!("ledgers_{}_{}", start, start + width - 1)
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::compress::CompressionStats;
+use crate::config::Config;
+use crate::round::{format_timestamp, Event, Round};
+use crate::session::{Restart, Session};
+use chrono::NaiveDateTime;
+use serde_json::{json, Value};
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+
+/// State of a pipeline after a block of the log, enough to continue reading after it
+pub struct Checkpoint {
+    // Settings of the run, resuming with other settings would give different output
+    pub settings: String,
+    // Bytes of the log processed, always the end of a line
+    pub offset: u64,
+    pub rounds: i32,
+    pub batches: i32,
+    pub started: bool,
+    pub last_timestamp: Option<NaiveDateTime>,
+    // Byte the run started reading at, past 0 after looking up a time range
+    pub start_offset: u64,
+    pub sessions: Vec<Session>,
+    pub templates: Vec<String>,
+    pub log_counts: Vec<u64>,
+    pub compression_stats: CompressionStats,
+    pub negative_rounds: u64,
+    // Rounds read since the last batch was handed over, the last one may still grow
+    pub partial: Vec<Round>,
+}
+
+/// Settings that change the output, the checkpoint and threading settings do not
+pub fn settings(config: &Config) -> String {
+    let mut config = config.clone();
+    config.checkpoint = 0;
+    config.resume = false;
+    config.threads = 0;
+    format!("{:?}", config)
+}
+
+pub fn checkpoint_filename(config: &Config) -> String {
+    format!("{}.checkpoint", config.filename)
+}
+
+fn parse_datetime(value: &Value) -> Option<NaiveDateTime> {
+    value
+        .as_str()
+        .and_then(|timestamp| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f").ok())
+}
+
+impl Checkpoint {
+    /// Write the checkpoint next to the log, replacing the previous one only once it is complete
+    pub fn write(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let partial = self
+            .partial
+            .iter()
+            .map(|item| {
+                json!({
+                    "index": item.index,
+                    "label": item.label,
+                    "start": format_timestamp(&item.start),
+                    "end": format_timestamp(&item.end),
+                    "ledger": item.ledger,
+                    "session": item.session,
+                    "status": item.status.to_string(),
+                    "mode": item.mode.map(|mode| mode.to_string()),
+                    "synced": item.synced,
+                    "events": item.events.iter().map(|event| json!({
+                        "log_id": event.log_id,
+                        "count": event.count,
+                        "timestamp": format_timestamp(&event.timestamp),
+                        "partition": event.partition,
+                        "severity": event.severity,
+                        "raw": event.raw,
+                    })).collect::<Vec<Value>>(),
+                })
+            })
+            .collect::<Vec<Value>>();
+        let sessions = self
+            .sessions
+            .iter()
+            .map(|session| {
+                json!({
+                    "index": session.index,
+                    "start": format_timestamp(&session.start),
+                    "reason": session.reason.map(|reason| reason.to_string()),
+                    "first_round": session.first_round,
+                })
+            })
+            .collect::<Vec<Value>>();
+        let checkpoint = json!({
+            "settings": self.settings,
+            "offset": self.offset,
+            "rounds": self.rounds,
+            "batches": self.batches,
+            "started": self.started,
+            "last_timestamp": format_timestamp(&self.last_timestamp),
+            "start_offset": self.start_offset,
+            "sessions": sessions,
+            "templates": self.templates,
+            "log_counts": self.log_counts,
+            "compression_events": self.compression_stats.events,
+            "compression_removed": self.compression_stats.removed,
+            "negative_rounds": self.negative_rounds,
+            "partial": partial,
+        });
+
+        let temporary = format!("{}.tmp", filename);
+        {
+            let mut checkpoint_file = BufWriter::new(File::create(&temporary)?);
+            serde_json::to_writer(&mut checkpoint_file, &checkpoint)?;
+            checkpoint_file.flush()?;
+            checkpoint_file.get_ref().sync_all()?;
+        }
+        fs::rename(temporary, filename)?;
+
+        Ok(())
+    }
+
+    pub fn read(filename: &str) -> Result<Checkpoint, Box<dyn std::error::Error>> {
+        let file = File::open(filename)?;
+        let value: Value = serde_json::from_reader(BufReader::new(file))?;
+        let invalid = || format!("invalid checkpoint {}", filename);
+        let number = |key: &str| value[key].as_u64().ok_or_else(invalid);
+
+        let strings = |value: &Value| -> Option<Vec<String>> {
+            value
+                .as_array()?
+                .iter()
+                .map(|item| item.as_str().map(|item| item.to_owned()))
+                .collect()
+        };
+        let partial = value["partial"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|item| {
+                let events = item["events"]
+                    .as_array()?
+                    .iter()
+                    .map(|event| {
+                        Some(Event {
+                            log_id: event["log_id"].as_u64()?,
+                            count: event["count"].as_u64()?,
+                            timestamp: parse_datetime(&event["timestamp"]),
+                            partition: event["partition"].as_str()?.to_owned(),
+                            severity: event["severity"].as_str()?.to_owned(),
+                            raw: event["raw"].as_str().map(|raw| raw.to_owned()),
+                        })
+                    })
+                    .collect::<Option<Vec<Event>>>()?;
+                Some(Round {
+                    index: item["index"].as_u64()? as usize,
+                    events,
+                    label: item["label"].as_u64()? as u8,
+                    start: parse_datetime(&item["start"]),
+                    end: parse_datetime(&item["end"]),
+                    ledger: item["ledger"].as_u64(),
+                    session: item["session"].as_u64()? as u32,
+                    status: item["status"].as_str()?.parse().ok()?,
+                    mode: match item["mode"].as_str() {
+                        Some(mode) => Some(mode.parse().ok()?),
+                        None => None,
+                    },
+                    synced: item["synced"].as_bool(),
+                })
+            })
+            .collect::<Option<Vec<Round>>>()
+            .ok_or_else(invalid)?;
+        let sessions = value["sessions"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|session| {
+                Some(Session {
+                    index: session["index"].as_u64()? as u32,
+                    start: parse_datetime(&session["start"]),
+                    reason: match session["reason"].as_str() {
+                        Some(reason) => Some(Restart::from_name(reason)?),
+                        None => None,
+                    },
+                    first_round: session["first_round"].as_u64().map(|index| index as usize),
+                })
+            })
+            .collect::<Option<Vec<Session>>>()
+            .ok_or_else(invalid)?;
+
+        Ok(Checkpoint {
+            settings: value["settings"].as_str().ok_or_else(invalid)?.to_owned(),
+            offset: number("offset")?,
+            rounds: number("rounds")? as i32,
+            batches: number("batches")? as i32,
+            started: value["started"].as_bool().ok_or_else(invalid)?,
+            last_timestamp: parse_datetime(&value["last_timestamp"]),
+            start_offset: number("start_offset")?,
+            sessions,
+            templates: strings(&value["templates"]).ok_or_else(invalid)?,
+            log_counts: value["log_counts"]
+                .as_array()
+                .and_then(|counts| counts.iter().map(|count| count.as_u64()).collect())
+                .ok_or_else(invalid)?,
+            compression_stats: CompressionStats {
+                events: number("compression_events")?,
+                removed: number("compression_removed")?,
+            },
+            negative_rounds: number("negative_rounds")?,
+            partial,
+        })
+    }
+}
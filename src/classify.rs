@@ -0,0 +1,197 @@
+use crate::round::{Round, Status};
+use crate::{is_label, map_log};
+use std::str::FromStr;
+
+// Labels of the events closing a round, the ledger it worked on is built or accepted
+static CLOSING_LABELS: &[&str] = &[
+    "consensusBuiltNewLedger",
+    "buildLedger",
+    "buildOldLedger",
+    "consensusBuiltLedgerWeHad",
+    "consensusBuiltLedgerWeAcquired",
+    "advancingLedger",
+    "ledgerAcceptedHash",
+];
+
+/// Rule marking a consensus round as negative (Abbadingo label 0) when it matches
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rule {
+    // Round contains an event with this label
+    Contains(String),
+    // Round ends without ever logging an event with this label
+    Missing(String),
+    // Round lasts longer than this amount of seconds
+    LongerThan(f64),
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rule, String> {
+        let parts = s.split_whitespace().collect::<Vec<&str>>();
+        match parts.as_slice() {
+            ["contains", label] | ["missing", label] if !is_label(label) => {
+                Err(format!("unknown label \"{}\"", label))
+            }
+            ["contains", label] => Ok(Rule::Contains(label.to_string())),
+            ["missing", label] => Ok(Rule::Missing(label.to_string())),
+            ["duration", ">", seconds] => match seconds.parse::<f64>() {
+                Ok(seconds) if seconds >= 0.0 => Ok(Rule::LongerThan(seconds)),
+                _ => Err(format!("invalid amount of seconds \"{}\"", seconds)),
+            },
+            _ => Err(format!(
+                "unknown rule \"{}\", expected one of contains <label>, missing <label>, duration > <seconds>",
+                s
+            )),
+        }
+    }
+}
+
+impl Rule {
+    fn matches(&self, round: &Round, labels: &[String]) -> bool {
+        match self {
+            Rule::Contains(label) => labels.contains(label),
+            Rule::Missing(label) => !labels.contains(label),
+            Rule::LongerThan(seconds) => match round.duration() {
+                Some(duration) => duration.num_milliseconds() as f64 > seconds * 1000.0,
+                None => false,
+            },
+        }
+    }
+}
+
+/// Abbadingo label of a round: 0 when any of the rules matches, 1 otherwise
+pub fn classify_round(round: &Round, rules: &[Rule], log_list: &[String]) -> u8 {
+    let labels = round.labels(log_list);
+
+    if rules.iter().any(|rule| rule.matches(round, &labels)) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Status of a round once it ended, `end` is what cut it off when that was not the next round; the
+/// round is complete when it built or accepted a ledger
+pub fn round_status(round: &Round, end: Option<Status>, log_list: &[String]) -> Status {
+    if let Some(end) = end {
+        return end;
+    }
+    if round.status == Status::Gap {
+        return Status::Gap;
+    }
+
+    let closed = round
+        .events
+        .iter()
+        .any(|event| CLOSING_LABELS.contains(&map_log(&event.log_id, log_list, false).as_str()));
+    if closed {
+        Status::Complete
+    } else {
+        Status::Unclosed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::round::{parse_timestamp, Event};
+
+    fn log_list() -> Vec<String> {
+        vec![
+            String::from("LedgerConsensus:NFO Entering consensus process, watching, synced=no"),
+            String::from("LedgerConsensus:DBG Bowing out of consensus"),
+            String::from("LedgerConsensus:DBG Consensus built new ledger"),
+        ]
+    }
+
+    /// Round with the events and a duration of `seconds`
+    fn round(log_ids: &[u64], seconds: u32) -> Round {
+        let start = parse_timestamp("2020-Mar-05 09:00:00.000000000");
+        let mut round = Round::new(0, 0, start);
+        round.end = parse_timestamp(&format!("2020-Mar-05 09:00:{:02}.000000000", seconds));
+        for log_id in log_ids.iter() {
+            round.events.push(Event {
+                log_id: *log_id,
+                count: 1,
+                timestamp: None,
+                partition: String::from("LedgerConsensus"),
+                severity: String::from("DBG"),
+                raw: None,
+            });
+        }
+        round
+    }
+
+    fn rule(s: &str) -> Rule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            rule("contains consensusBowOut"),
+            Rule::Contains(String::from("consensusBowOut"))
+        );
+        assert_eq!(
+            rule("  missing   consensusBuiltNewLedger "),
+            Rule::Missing(String::from("consensusBuiltNewLedger"))
+        );
+        assert_eq!(rule("duration > 2.5"), Rule::LongerThan(2.5));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!("contains consensusBowout".parse::<Rule>().is_err());
+        assert!("missing notALabel".parse::<Rule>().is_err());
+        assert!("contains".parse::<Rule>().is_err());
+        assert!("duration > -1".parse::<Rule>().is_err());
+        assert!("duration > soon".parse::<Rule>().is_err());
+        assert!("duration < 10".parse::<Rule>().is_err());
+        assert!("longer 10".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn classifies_rounds_by_rules() {
+        let log_list = log_list();
+        let bow_out = round(&[0, 1, 2], 3);
+        let built = round(&[0, 2], 3);
+        let unclosed = round(&[0], 12);
+
+        let contains = [rule("contains consensusBowOut")];
+        assert_eq!(classify_round(&bow_out, &contains, &log_list), 0);
+        assert_eq!(classify_round(&built, &contains, &log_list), 1);
+
+        let missing = [rule("missing consensusBuiltNewLedger")];
+        assert_eq!(classify_round(&built, &missing, &log_list), 1);
+        assert_eq!(classify_round(&unclosed, &missing, &log_list), 0);
+
+        let duration = [rule("duration > 10")];
+        assert_eq!(classify_round(&built, &duration, &log_list), 1);
+        assert_eq!(classify_round(&unclosed, &duration, &log_list), 0);
+
+        assert_eq!(classify_round(&bow_out, &[], &log_list), 1);
+        let any = [contains[0].clone(), duration[0].clone()];
+        assert_eq!(classify_round(&bow_out, &any, &log_list), 0);
+        assert_eq!(classify_round(&unclosed, &any, &log_list), 0);
+        assert_eq!(classify_round(&built, &any, &log_list), 1);
+    }
+
+    #[test]
+    fn status_depends_on_closing_events() {
+        let log_list = log_list();
+        let built = round(&[0, 2], 3);
+        let unclosed = round(&[0, 1], 3);
+
+        assert_eq!(round_status(&built, None, &log_list), Status::Complete);
+        assert_eq!(round_status(&unclosed, None, &log_list), Status::Unclosed);
+        assert_eq!(
+            round_status(&built, Some(Status::Restart), &log_list),
+            Status::Restart
+        );
+
+        let mut gap = round(&[0, 2], 3);
+        gap.status = Status::Gap;
+        assert_eq!(round_status(&gap, None, &log_list), Status::Gap);
+    }
+}
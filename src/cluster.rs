@@ -0,0 +1,223 @@
+use crate::config::Config;
+use crate::pipeline::Pipeline;
+use crate::round::{format_timestamp, Round};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::str::FromStr;
+
+// Rounds of k-medoids before giving up on convergence
+static MAX_ITERATIONS: usize = 100;
+
+/// Distance between the label sequences of two rounds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Distance {
+    // Insertions, deletions and substitutions turning one sequence into the other
+    #[default]
+    Edit,
+    // Difference in the amount of every label, ignoring their order
+    Histogram,
+}
+
+impl FromStr for Distance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Distance, String> {
+        match s.trim() {
+            "edit" => Ok(Distance::Edit),
+            "histogram" => Ok(Distance::Histogram),
+            _ => Err(format!(
+                "unknown distance \"{}\", expected edit or histogram",
+                s
+            )),
+        }
+    }
+}
+
+impl Distance {
+    fn between(&self, a: &[usize], b: &[usize], alphabet_size: usize) -> u64 {
+        match self {
+            Distance::Edit => {
+                let mut previous = (0..=b.len() as u64).collect::<Vec<u64>>();
+                for (i, symbol_a) in a.iter().enumerate() {
+                    let mut current = vec![i as u64 + 1; b.len() + 1];
+                    for (j, symbol_b) in b.iter().enumerate() {
+                        let substitution = previous[j] + (symbol_a != symbol_b) as u64;
+                        current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+                    }
+                    previous = current;
+                }
+                previous[b.len()]
+            }
+            Distance::Histogram => {
+                let mut histogram = vec![0i64; alphabet_size];
+                for symbol in a.iter() {
+                    histogram[*symbol] += 1;
+                }
+                for symbol in b.iter() {
+                    histogram[*symbol] -= 1;
+                }
+                histogram.iter().map(|count| count.unsigned_abs()).sum()
+            }
+        }
+    }
+}
+
+/// Split the weighted sequences into k clusters around medoids, returns the medoids and the
+/// cluster of every sequence
+fn k_medoids(distances: &[Vec<u64>], weights: &[u64], k: usize) -> (Vec<usize>, Vec<usize>) {
+    let n = distances.len();
+    let cost = |candidate: usize, nearest: &[u64]| -> u64 {
+        (0..n)
+            .map(|i| weights[i] * nearest[i].min(distances[i][candidate]))
+            .sum()
+    };
+
+    // Greedily pick the medoids lowering the total distance most
+    let mut medoids = Vec::<usize>::new();
+    let mut nearest = vec![u64::MAX; n];
+    while medoids.len() < k.min(n) {
+        let best = (0..n)
+            .filter(|candidate| !medoids.contains(candidate))
+            .min_by_key(|candidate| cost(*candidate, &nearest))
+            .unwrap();
+        medoids.push(best);
+        for i in 0..n {
+            nearest[i] = nearest[i].min(distances[i][best]);
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for _ in 0..MAX_ITERATIONS {
+        for (i, cluster) in assignment.iter_mut().enumerate() {
+            *cluster = (0..medoids.len())
+                .min_by_key(|cluster| distances[i][medoids[*cluster]])
+                .unwrap();
+        }
+
+        // Move every medoid to the member closest to all other members
+        let mut changed = false;
+        for (cluster, medoid) in medoids.iter_mut().enumerate() {
+            let members = (0..n)
+                .filter(|i| assignment[*i] == cluster)
+                .collect::<Vec<usize>>();
+            let best = *members
+                .iter()
+                .min_by_key(|candidate| {
+                    members
+                        .iter()
+                        .map(|i| weights[*i] * distances[*i][**candidate])
+                        .sum::<u64>()
+                })
+                .unwrap_or(medoid);
+            if best != *medoid {
+                *medoid = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    (medoids, assignment)
+}
+
+/// Cluster the rounds by the distance between their label sequences, every cluster is written to
+/// `.clusters` with its size, medoid round and the time range of every member
+pub fn cluster(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut symbols = HashMap::<String, usize>::new();
+    let mut rounds = Vec::<(Round, Vec<String>)>::new();
+
+    let mut pipeline = Pipeline::new();
+    pipeline.run(config, |batch, log_list| {
+        for item in batch.rounds.into_iter() {
+            let labels = item.labels(log_list);
+            rounds.push((item, labels));
+        }
+        Ok(())
+    })?;
+
+    // Rounds with the same labels are clustered once, weighted by how often they occur
+    let mut sequences = Vec::<Vec<usize>>::new();
+    let mut members = Vec::<Vec<usize>>::new();
+    let mut sequence_ids = HashMap::<Vec<usize>, usize>::new();
+    for (index, (_, labels)) in rounds.iter().enumerate() {
+        let sequence = labels
+            .iter()
+            .map(|label| {
+                let next = symbols.len();
+                *symbols.entry(label.clone()).or_insert(next)
+            })
+            .collect::<Vec<usize>>();
+        let id = *sequence_ids.entry(sequence.clone()).or_insert_with(|| {
+            sequences.push(sequence);
+            members.push(Vec::new());
+            sequences.len() - 1
+        });
+        members[id].push(index);
+    }
+
+    let distances = sequences
+        .iter()
+        .map(|a| {
+            sequences
+                .iter()
+                .map(|b| config.distance.between(a, b, symbols.len()))
+                .collect()
+        })
+        .collect::<Vec<Vec<u64>>>();
+    let weights = members
+        .iter()
+        .map(|rounds| rounds.len() as u64)
+        .collect::<Vec<u64>>();
+    let (medoids, assignment) = k_medoids(&distances, &weights, config.clusters);
+
+    // Largest clusters first
+    let mut clusters = medoids
+        .iter()
+        .enumerate()
+        .map(|(cluster, medoid)| {
+            let sequences = (0..sequences.len())
+                .filter(|sequence| assignment[*sequence] == cluster)
+                .collect::<Vec<usize>>();
+            let mut rounds = sequences
+                .iter()
+                .flat_map(|sequence| members[*sequence].iter().cloned())
+                .collect::<Vec<usize>>();
+            rounds.sort_unstable();
+            (*medoid, rounds)
+        })
+        .collect::<Vec<(usize, Vec<usize>)>>();
+    clusters.sort_by_key(|(_, rounds)| std::cmp::Reverse(rounds.len()));
+
+    let time_range = |item: &Round| {
+        format!(
+            "{} {}",
+            format_timestamp(&item.start).unwrap_or_else(|| String::from("-")),
+            format_timestamp(&item.end).unwrap_or_else(|| String::from("-"))
+        )
+    };
+    let clusters_file = File::create(format!("{}.clusters", config.filename))?;
+    let mut clusters_file = BufWriter::new(clusters_file);
+    for (cluster, (medoid, cluster_rounds)) in clusters.iter().enumerate() {
+        let (medoid_round, medoid_labels) = &rounds[members[*medoid][0]];
+        let line = format!(
+            "cluster {} size {} medoid round {} {}",
+            cluster,
+            cluster_rounds.len(),
+            medoid_round.index,
+            time_range(medoid_round)
+        );
+        writeln!(clusters_file, "{}", line)?;
+        writeln!(clusters_file, "  labels {}", medoid_labels.join(" "))?;
+        for member in cluster_rounds.iter() {
+            let (item, _) = &rounds[*member];
+            writeln!(clusters_file, "  round {} {}", item.index, time_range(item))?;
+        }
+        println!("{} labels {}", line, medoid_labels.join(" "));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,292 @@
+use crate::config::Config;
+use crate::map_log;
+use crate::round::Round;
+use chrono::NaiveDateTime;
+use parquet::basic::Compression;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::fs::File;
+use std::sync::Arc;
+
+static EVENTS_SCHEMA: &str = "
+    message event {
+        REQUIRED BYTE_ARRAY run (UTF8);
+        REQUIRED BYTE_ARRAY node (UTF8);
+        REQUIRED INT64 round;
+        REQUIRED INT64 position;
+        OPTIONAL INT64 timestamp (TIMESTAMP(NANOS,false));
+        REQUIRED INT64 template_id;
+        REQUIRED BYTE_ARRAY label (UTF8);
+        REQUIRED BYTE_ARRAY partition (UTF8);
+        REQUIRED BYTE_ARRAY severity (UTF8);
+        REQUIRED INT64 count;
+        OPTIONAL BYTE_ARRAY raw (UTF8);
+    }
+";
+
+static ROUNDS_SCHEMA: &str = "
+    message round {
+        REQUIRED BYTE_ARRAY run (UTF8);
+        REQUIRED BYTE_ARRAY node (UTF8);
+        REQUIRED INT64 round;
+        REQUIRED INT64 session;
+        OPTIONAL INT64 start (TIMESTAMP(NANOS,false));
+        OPTIONAL INT64 end (TIMESTAMP(NANOS,false));
+        REQUIRED INT64 label;
+        REQUIRED BYTE_ARRAY status (UTF8);
+        OPTIONAL BYTE_ARRAY mode (UTF8);
+        OPTIONAL BOOLEAN synced;
+        REQUIRED INT64 events;
+    }
+";
+
+/// Values of a single column in a row group, optional columns keep their definition levels
+enum Column {
+    Int64(Vec<i64>, Option<Vec<i16>>),
+    Bytes(Vec<ByteArray>, Option<Vec<i16>>),
+    Bool(Vec<bool>, Option<Vec<i16>>),
+}
+
+impl Column {
+    fn required_int64(values: Vec<i64>) -> Column {
+        Column::Int64(values, None)
+    }
+
+    fn optional_int64(values: Vec<Option<i64>>) -> Column {
+        let levels = values.iter().map(|value| value.is_some() as i16).collect();
+        Column::Int64(values.into_iter().flatten().collect(), Some(levels))
+    }
+
+    fn required_bytes(values: Vec<&str>) -> Column {
+        Column::Bytes(values.into_iter().map(ByteArray::from).collect(), None)
+    }
+
+    fn optional_bytes(values: Vec<Option<&str>>) -> Column {
+        let levels = values.iter().map(|value| value.is_some() as i16).collect();
+        Column::Bytes(
+            values.into_iter().flatten().map(ByteArray::from).collect(),
+            Some(levels),
+        )
+    }
+
+    fn optional_bool(values: Vec<Option<bool>>) -> Column {
+        let levels = values.iter().map(|value| value.is_some() as i16).collect();
+        Column::Bool(values.into_iter().flatten().collect(), Some(levels))
+    }
+}
+
+fn nanos(timestamp: &Option<NaiveDateTime>) -> Option<i64> {
+    timestamp.and_then(|timestamp| timestamp.and_utc().timestamp_nanos_opt())
+}
+
+/// Append the columns as a row group, in the order of the schema
+fn write_row_group(
+    writer: &mut SerializedFileWriter<File>,
+    columns: Vec<Column>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut row_group_writer = writer.next_row_group()?;
+    for column in columns.into_iter() {
+        let mut column_writer = row_group_writer.next_column()?.unwrap();
+        match column {
+            Column::Int64(values, levels) => {
+                column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, levels.as_deref(), None)?;
+            }
+            Column::Bytes(values, levels) => {
+                column_writer.typed::<ByteArrayType>().write_batch(
+                    &values,
+                    levels.as_deref(),
+                    None,
+                )?;
+            }
+            Column::Bool(values, levels) => {
+                column_writer
+                    .typed::<BoolType>()
+                    .write_batch(&values, levels.as_deref(), None)?;
+            }
+        }
+        column_writer.close()?;
+    }
+    row_group_writer.close()?;
+
+    Ok(())
+}
+
+/// Parquet files with one row per event and one row per round for the entire run, every batch is
+/// added as a row group
+pub struct ParquetWriter {
+    events: SerializedFileWriter<File>,
+    rounds: SerializedFileWriter<File>,
+    run: String,
+    node: String,
+}
+
+impl ParquetWriter {
+    pub fn create(
+        filename: &str,
+        config: &Config,
+    ) -> Result<ParquetWriter, Box<dyn std::error::Error>> {
+        let properties = Arc::new(
+            WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build(),
+        );
+        let events = SerializedFileWriter::new(
+            File::create(filename.to_owned() + ".events.parquet")?,
+            Arc::new(parse_message_type(EVENTS_SCHEMA)?),
+            properties.clone(),
+        )?;
+        let rounds = SerializedFileWriter::new(
+            File::create(filename.to_owned() + ".rounds.parquet")?,
+            Arc::new(parse_message_type(ROUNDS_SCHEMA)?),
+            properties,
+        )?;
+
+        Ok(ParquetWriter {
+            events,
+            rounds,
+            run: config.filename.clone(),
+            node: config.node.clone(),
+        })
+    }
+
+    pub fn write_batch(
+        &mut self,
+        all_log_sequence: &[Round],
+        log_list: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let events = all_log_sequence
+            .iter()
+            .flat_map(|item| {
+                item.events
+                    .iter()
+                    .enumerate()
+                    .map(move |event| (item, event))
+            })
+            .collect::<Vec<_>>();
+        let labels = events
+            .iter()
+            .map(|(_, (_, event))| map_log(&event.log_id, log_list, false))
+            .collect::<Vec<String>>();
+
+        write_row_group(
+            &mut self.events,
+            vec![
+                Column::required_bytes(vec![&self.run; events.len()]),
+                Column::required_bytes(vec![&self.node; events.len()]),
+                Column::required_int64(events.iter().map(|(item, _)| item.index as i64).collect()),
+                Column::required_int64(
+                    events
+                        .iter()
+                        .map(|(_, (position, _))| *position as i64)
+                        .collect(),
+                ),
+                Column::optional_int64(
+                    events
+                        .iter()
+                        .map(|(_, (_, event))| nanos(&event.timestamp))
+                        .collect(),
+                ),
+                Column::required_int64(
+                    events
+                        .iter()
+                        .map(|(_, (_, event))| event.log_id as i64)
+                        .collect(),
+                ),
+                Column::required_bytes(labels.iter().map(|label| label.as_str()).collect()),
+                Column::required_bytes(
+                    events
+                        .iter()
+                        .map(|(_, (_, event))| event.partition.as_str())
+                        .collect(),
+                ),
+                Column::required_bytes(
+                    events
+                        .iter()
+                        .map(|(_, (_, event))| event.severity.as_str())
+                        .collect(),
+                ),
+                Column::required_int64(
+                    events
+                        .iter()
+                        .map(|(_, (_, event))| event.count as i64)
+                        .collect(),
+                ),
+                Column::optional_bytes(
+                    events
+                        .iter()
+                        .map(|(_, (_, event))| event.raw.as_deref())
+                        .collect(),
+                ),
+            ],
+        )?;
+
+        let statuses = all_log_sequence
+            .iter()
+            .map(|item| item.status.to_string())
+            .collect::<Vec<String>>();
+        let modes = all_log_sequence
+            .iter()
+            .map(|item| item.mode.map(|mode| mode.to_string()))
+            .collect::<Vec<Option<String>>>();
+        write_row_group(
+            &mut self.rounds,
+            vec![
+                Column::required_bytes(vec![&self.run; all_log_sequence.len()]),
+                Column::required_bytes(vec![&self.node; all_log_sequence.len()]),
+                Column::required_int64(
+                    all_log_sequence
+                        .iter()
+                        .map(|item| item.index as i64)
+                        .collect(),
+                ),
+                Column::required_int64(
+                    all_log_sequence
+                        .iter()
+                        .map(|item| item.session as i64)
+                        .collect(),
+                ),
+                Column::optional_int64(
+                    all_log_sequence
+                        .iter()
+                        .map(|item| nanos(&item.start))
+                        .collect(),
+                ),
+                Column::optional_int64(
+                    all_log_sequence
+                        .iter()
+                        .map(|item| nanos(&item.end))
+                        .collect(),
+                ),
+                Column::required_int64(
+                    all_log_sequence
+                        .iter()
+                        .map(|item| item.label as i64)
+                        .collect(),
+                ),
+                Column::required_bytes(statuses.iter().map(|status| status.as_str()).collect()),
+                Column::optional_bytes(modes.iter().map(|mode| mode.as_deref()).collect()),
+                Column::optional_bool(all_log_sequence.iter().map(|item| item.synced).collect()),
+                Column::required_int64(
+                    all_log_sequence
+                        .iter()
+                        .map(|item| item.events.len() as i64)
+                        .collect(),
+                ),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Write the file footers, the files are unreadable without them
+    pub fn close(self) -> Result<(), Box<dyn std::error::Error>> {
+        self.events.close()?;
+        self.rounds.close()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use crate::map_log;
+use crate::round::{Event, Round};
+use std::fmt;
+use std::str::FromStr;
+
+/// Policy for compressing repeated events within a consensus round
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    // Keep every event
+    None,
+    // Keep at most k consecutive identical log ids
+    Cap(u64),
+    // Collapse consecutive identical log ids into a single event
+    Collapse,
+    // Collapse consecutive identical log ids, keeping the run length as event attribute
+    RunLength,
+    // Collapse consecutive events that map to the same label
+    Label,
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::Cap(2)
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Compression, String> {
+        match s {
+            "none" => Ok(Compression::None),
+            "collapse" => Ok(Compression::Collapse),
+            "rle" => Ok(Compression::RunLength),
+            "label" => Ok(Compression::Label),
+            _ if s.starts_with("cap:") => match s["cap:".len()..].parse::<u64>() {
+                Ok(k) if k > 0 => Ok(Compression::Cap(k)),
+                _ => Err(format!(
+                    "invalid cap \"{}\", expected cap:<k> with k > 0",
+                    s
+                )),
+            },
+            _ => Err(format!(
+                "unknown compression \"{}\", expected one of none, cap:<k>, collapse, rle, label",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Compression::None => write!(f, "none"),
+            Compression::Cap(k) => write!(f, "cap:{}", k),
+            Compression::Collapse => write!(f, "collapse"),
+            Compression::RunLength => write!(f, "rle"),
+            Compression::Label => write!(f, "label"),
+        }
+    }
+}
+
+/// Counts of events seen and removed by compression
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompressionStats {
+    pub events: u64,
+    pub removed: u64,
+}
+
+/// Compress a single round according to the policy, returning the compressed round
+pub fn compress_round(
+    round: &Round,
+    compression: Compression,
+    log_list: &[String],
+    stats: &mut CompressionStats,
+) -> Round {
+    let mut new_events = Vec::<Event>::new();
+    // Length of the run the last kept event belongs to
+    let mut run = 0;
+
+    for event in round.events.iter() {
+        let repeats = match new_events.last() {
+            Some(prev) => match compression {
+                Compression::Label => {
+                    map_log(&prev.log_id, log_list, false)
+                        == map_log(&event.log_id, log_list, false)
+                }
+                _ => prev.log_id == event.log_id,
+            },
+            None => false,
+        };
+        run = if repeats { run + 1 } else { 1 };
+
+        let keep = match compression {
+            Compression::None => true,
+            Compression::Cap(k) => run <= k,
+            Compression::Collapse | Compression::RunLength | Compression::Label => run == 1,
+        };
+
+        if keep {
+            new_events.push(event.clone());
+        } else if compression == Compression::RunLength {
+            new_events.last_mut().unwrap().count += event.count;
+        }
+    }
+
+    stats.events += round.events.len() as u64;
+    stats.removed += (round.events.len() - new_events.len()) as u64;
+
+    let mut new_round = round.clone();
+    new_round.events = new_events;
+    new_round
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_list() -> Vec<String> {
+        vec![
+            // Both map to the acquireTimeout label
+            String::from("LedgerConsensus:DBG Acquire #some-base-16-hash timeouts:3 good:#some-good-num dupe:#some-dupe-num"),
+            String::from("LedgerConsensus:DBG Acquire #some-base-16-hash timeouts:2 good:#some-good-num dupe:#some-dupe-num"),
+            String::from("LedgerConsensus:DBG Consensus built new ledger"),
+        ]
+    }
+
+    fn round(log_ids: &[u64]) -> Round {
+        let mut round = Round::new(0, 0, None);
+        for log_id in log_ids.iter() {
+            round.events.push(Event {
+                log_id: *log_id,
+                count: 1,
+                timestamp: None,
+                partition: String::from("LedgerConsensus"),
+                severity: String::from("DBG"),
+                raw: None,
+            });
+        }
+        round
+    }
+
+    /// Log ids and counts of the events left by the policy, and the number of events removed
+    fn compress(compression: Compression) -> (Vec<(u64, u64)>, u64) {
+        let mut stats = CompressionStats::default();
+        let compressed = compress_round(
+            &round(&[0, 0, 0, 1, 2, 2, 0]),
+            compression,
+            &log_list(),
+            &mut stats,
+        );
+        assert_eq!(stats.events, 7);
+        let events = compressed
+            .events
+            .iter()
+            .map(|event| (event.log_id, event.count))
+            .collect();
+        (events, stats.removed)
+    }
+
+    #[test]
+    fn none_keeps_every_event() {
+        let (events, removed) = compress(Compression::None);
+        assert_eq!(events.len(), 7);
+        assert_eq!(removed, 0);
+    }
+
+    #[test]
+    fn cap_keeps_k_identical_ids_in_a_row() {
+        assert_eq!(
+            compress(Compression::Cap(2)),
+            (vec![(0, 1), (0, 1), (1, 1), (2, 1), (2, 1), (0, 1)], 1)
+        );
+        assert_eq!(
+            compress(Compression::Cap(1)),
+            (vec![(0, 1), (1, 1), (2, 1), (0, 1)], 3)
+        );
+    }
+
+    #[test]
+    fn collapse_keeps_one_event_per_run() {
+        assert_eq!(
+            compress(Compression::Collapse),
+            (vec![(0, 1), (1, 1), (2, 1), (0, 1)], 3)
+        );
+    }
+
+    #[test]
+    fn run_length_counts_the_collapsed_events() {
+        assert_eq!(
+            compress(Compression::RunLength),
+            (vec![(0, 3), (1, 1), (2, 2), (0, 1)], 3)
+        );
+    }
+
+    #[test]
+    fn label_collapses_ids_with_the_same_label() {
+        assert_eq!(
+            compress(Compression::Label),
+            (vec![(0, 1), (2, 1), (0, 1)], 4)
+        );
+    }
+
+    #[test]
+    fn stats_add_up_over_rounds() {
+        let mut stats = CompressionStats::default();
+        for _ in 0..2 {
+            compress_round(
+                &round(&[2, 2, 2]),
+                Compression::Collapse,
+                &log_list(),
+                &mut stats,
+            );
+        }
+        assert_eq!((stats.events, stats.removed), (6, 4));
+    }
+
+    #[test]
+    fn parse_compression() {
+        for s in ["none", "cap:3", "collapse", "rle", "label"] {
+            assert_eq!(s.parse::<Compression>().unwrap().to_string(), s);
+        }
+        assert!("cap:0".parse::<Compression>().is_err());
+        assert!("cap".parse::<Compression>().is_err());
+        assert!("zip".parse::<Compression>().is_err());
+    }
+}
@@ -0,0 +1,440 @@
+use crate::batching::Batching;
+use crate::classify::Rule;
+use crate::cluster::Distance;
+use crate::compress::Compression;
+use crate::filter::Interval;
+use crate::learn::Learner;
+use crate::ngram::Ranking;
+use crate::query::Pattern;
+use crate::round::Mode;
+use crate::split::Split;
+use crate::windows::WindowScope;
+use chrono::NaiveDateTime;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+use std::str::FromStr;
+
+// Stop after number rounds
+static ROUNDS_PER_BATCH: i32 = 20;
+static AMOUNT_BATCHES: i32 = 10;
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum ConfigError {
+        Io(err: io::Error) {
+            from()
+            display("could not read config: {}", err)
+        }
+        MissingArgument(what: &'static str) {
+            display("missing argument for {}", what)
+        }
+        UnknownCommand(command: String) {
+            display("unknown command \"{}\", expected learn, conformance, score, cluster or query", command)
+        }
+        UnknownKey(key: String) {
+            display("unknown configuration key \"{}\"", key)
+        }
+        InvalidValue(key: String, reason: String) {
+            display("invalid value for \"{}\": {}", key, reason)
+        }
+    }
+}
+
+/// Output format written for every batch of rounds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    // .parsed, .labeled and .mapping files for state machine learners
+    Abbadingo,
+    // .jsonl file with one object per round, including all event details
+    Jsonl,
+    // .xes event log for process mining tools
+    Xes,
+    // .sqlite database for the entire run, batches are added as they are written
+    Sqlite,
+    // .events.parquet and .rounds.parquet for the entire run, only with the parquet feature
+    Parquet,
+    // .markov.csv and .markov.dot label transition counts, per batch and for the entire run
+    Markov,
+    // .windows next event prediction rows and the .vocab of their template ids
+    Windows,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s.trim() {
+            "abbadingo" => Ok(Format::Abbadingo),
+            "jsonl" => Ok(Format::Jsonl),
+            "xes" => Ok(Format::Xes),
+            "sqlite" => Ok(Format::Sqlite),
+            "markov" => Ok(Format::Markov),
+            "windows" => Ok(Format::Windows),
+            "parquet" if cfg!(feature = "parquet") => Ok(Format::Parquet),
+            "parquet" => Err(String::from(
+                "parquet is not available, build with `--features parquet`",
+            )),
+            _ => Err(format!(
+                "unknown format \"{}\", expected one of abbadingo, jsonl, xes, sqlite, parquet, markov, windows",
+                s
+            )),
+        }
+    }
+}
+
+/// What to do with the rounds of the log
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    // Write the rounds in the configured formats, used when no command is given
+    Convert,
+    // Learn an automaton from the rounds
+    Learn,
+    // Replay the rounds through a reference automaton
+    Conformance,
+    // Rank the rounds by their likelihood under an n-gram model of reference logs
+    Score,
+    // Group the rounds with similar label sequences
+    Cluster,
+    // Print the rounds matching a pattern over their labels
+    Query,
+}
+
+/// Settings for a run, read from an optional config file and overridden by command line flags
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub command: Command,
+    pub filename: String,
+    // Rounds per batch when batching by rounds
+    pub rounds_per_batch: i32,
+    pub batching: Batching,
+    // Process entire file when set to -1
+    pub amount_batches: i32,
+    pub compression: Compression,
+    // Rules labeling a round as negative, each `negative` key adds one
+    pub negative: Vec<Rule>,
+    // Export one shuffled dataset instead of batches when set
+    pub split: Option<Split>,
+    // Seed for shuffling the rounds of a dataset export
+    pub seed: u64,
+    pub formats: Vec<Format>,
+    // Name of the node that wrote the log, defaults to the name of the logfile
+    pub node: String,
+    // Keep the original message of every event for outputs that include it
+    pub raw_messages: bool,
+    // State merging strategy of the learn command
+    pub learner: Learner,
+    // Reference automaton of the conformance command, .json or .dot
+    pub model: Option<String>,
+    // Logs the n-gram model of the score command is trained on, each `reference` key adds one
+    pub reference: Vec<String>,
+    // Length of the n-grams, including the label they predict
+    pub ngram: usize,
+    pub ranking: Ranking,
+    // Amount of ranked rounds printed by the score command
+    pub top: usize,
+    // Amount of template ids preceding the predicted one in the windows format
+    pub window: usize,
+    pub window_scope: WindowScope,
+    // Write identical rounds once in the .parsed and .labeled files
+    pub dedup: bool,
+    // Amount of clusters of the cluster command
+    pub clusters: usize,
+    pub distance: Distance,
+    // Labels searched for by the query command
+    pub pattern: Option<Pattern>,
+    // Worker threads parsing and sanitizing lines, 0 uses one per core
+    pub threads: usize,
+    // Only rounds starting within this time range are kept
+    pub time_range: Option<Interval<NaiveDateTime>>,
+    // Only rounds working on a ledger within this range of sequences are kept
+    pub ledger_range: Option<Interval<u64>>,
+    // Only rounds with an index within this range are kept
+    pub round_range: Option<Interval<u64>>,
+    // Seconds without log lines after which the node is taken to be restarted, 0 only detects
+    // timestamps going back
+    pub restart_gap: u64,
+    // Seconds between two lines of a round after which the round is incomplete, 0 checks no gaps
+    pub max_gap: u64,
+    // Leave out the rounds that are not complete
+    pub drop_incomplete: bool,
+    // Only rounds in this mode are kept
+    pub mode: Option<Mode>,
+    // Only rounds with this sync state are kept
+    pub synced: Option<bool>,
+    // Write the rounds of every mode and sync state to their own files
+    pub group_by_mode: bool,
+    // Seconds between checkpoints of the run, 0 writes none
+    pub checkpoint: u64,
+    // Continue from the checkpoint of an interrupted run
+    pub resume: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            command: Command::Convert,
+            filename: String::new(),
+            rounds_per_batch: ROUNDS_PER_BATCH,
+            batching: Batching::default(),
+            amount_batches: AMOUNT_BATCHES,
+            compression: Compression::default(),
+            negative: Vec::new(),
+            split: None,
+            seed: 0,
+            formats: vec![Format::Abbadingo],
+            node: String::new(),
+            raw_messages: false,
+            learner: Learner::default(),
+            model: None,
+            reference: Vec::new(),
+            ngram: 3,
+            ranking: Ranking::default(),
+            top: 20,
+            window: 10,
+            window_scope: WindowScope::default(),
+            dedup: false,
+            clusters: 5,
+            distance: Distance::default(),
+            pattern: None,
+            threads: 0,
+            time_range: None,
+            ledger_range: None,
+            round_range: None,
+            restart_gap: 0,
+            max_gap: 0,
+            drop_incomplete: false,
+            mode: None,
+            synced: None,
+            group_by_mode: false,
+            checkpoint: 0,
+            resume: false,
+        }
+    }
+}
+
+impl Config {
+    /// Build the config from the command line: `[<command>] [--config <file>] [--<key> <value>]... <logfile>`
+    pub fn from_args(args: &[String]) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        let mut config_files = Vec::<String>::new();
+        let mut overrides = Vec::<(String, String)>::new();
+        let mut positional = Vec::<String>::new();
+
+        // First argument is the command itself
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg.to_owned());
+                continue;
+            }
+
+            let key = arg.trim_start_matches("--");
+            let value = args.next().ok_or(ConfigError::MissingArgument("flag"))?;
+            if key == "config" {
+                config_files.push(value.to_owned());
+            } else {
+                overrides.push((key.to_owned(), value.to_owned()));
+            }
+        }
+
+        // The logfile is always the last positional argument, optionally preceded by a command
+        let filename = positional.pop();
+        config.command = match positional.as_slice() {
+            [] => Command::Convert,
+            [command] if command == "learn" => Command::Learn,
+            [command] if command == "conformance" => Command::Conformance,
+            [command] if command == "score" => Command::Score,
+            [command] if command == "cluster" => Command::Cluster,
+            [command] if command == "query" => Command::Query,
+            [command, ..] => return Err(ConfigError::UnknownCommand(command.to_owned())),
+        };
+        // The commands analyse the whole log unless `batches` is given
+        if config.command != Command::Convert {
+            config.amount_batches = -1;
+        }
+
+        for path in config_files.iter() {
+            config.load(path)?;
+        }
+        // Flags take precedence over the config file, regardless of their position
+        for (key, value) in overrides.iter() {
+            config.set(key, value)?;
+        }
+        config.filename = filename.ok_or(ConfigError::MissingArgument("logfile"))?;
+        if config.command == Command::Conformance && config.model.is_none() {
+            return Err(ConfigError::MissingArgument("model"));
+        }
+        if config.command == Command::Score && config.reference.is_empty() {
+            return Err(ConfigError::MissingArgument("reference"));
+        }
+        if config.command == Command::Query && config.pattern.is_none() {
+            return Err(ConfigError::MissingArgument("pattern"));
+        }
+        // Only batch files and the database can be continued, other outputs need the whole run
+        if config.checkpoint > 0 || config.resume {
+            let key = if config.resume {
+                "resume"
+            } else {
+                "checkpoint"
+            };
+            let reason = if config.command != Command::Convert {
+                Some("only available without a command")
+            } else if config.split.is_some() {
+                Some("not available together with split")
+            } else if config.formats.contains(&Format::Parquet) {
+                Some("not available for the parquet format")
+            } else if config.formats.contains(&Format::Markov) {
+                Some("not available for the markov format")
+            } else if config.formats.contains(&Format::Windows)
+                && config.window_scope == WindowScope::Stream
+            {
+                Some("not available for the windows format with the stream scope")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(ConfigError::InvalidValue(
+                    key.to_owned(),
+                    String::from(reason),
+                ));
+            }
+        }
+        // Windows predict the event that actually followed, compression would leave events out
+        if config.command == Command::Convert
+            && config.formats.contains(&Format::Windows)
+            && config.compression != Compression::None
+        {
+            return Err(ConfigError::InvalidValue(
+                String::from("compression"),
+                String::from("the windows format needs compression none"),
+            ));
+        }
+        if config.node.is_empty() {
+            config.node = match Path::new(&config.filename).file_stem() {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => config.filename.clone(),
+            };
+        }
+        Ok(config)
+    }
+
+    /// Read `key = value` lines from a config file, lines starting with `#` are comments
+    pub fn load(&mut self, path: &str) -> Result<(), ConfigError> {
+        let file = File::open(path)?;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.find('=') {
+                Some(pos) => self.set(line[..pos].trim(), line[pos + 1..].trim())?,
+                None => {
+                    return Err(ConfigError::InvalidValue(
+                        line.to_owned(),
+                        String::from("expected key = value"),
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = |reason: String| ConfigError::InvalidValue(key.to_owned(), reason);
+        match key {
+            "rounds-per-batch" => {
+                self.rounds_per_batch = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            "batch-by" => self.batching = value.parse().map_err(invalid)?,
+            "batches" => {
+                self.amount_batches = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            "compression" => self.compression = value.parse().map_err(invalid)?,
+            "negative" => self.negative.push(value.parse().map_err(invalid)?),
+            "split" => self.split = Some(value.parse().map_err(invalid)?),
+            "seed" => self.seed = value.parse().map_err(|_| invalid(value.to_owned()))?,
+            "formats" => {
+                self.formats = value
+                    .split(',')
+                    .map(|format| format.parse())
+                    .collect::<Result<Vec<Format>, String>>()
+                    .map_err(invalid)?
+            }
+            "node" => self.node = value.to_owned(),
+            "learner" => self.learner = value.parse().map_err(invalid)?,
+            "model" => self.model = Some(value.to_owned()),
+            "reference" => self.reference.push(value.to_owned()),
+            "ngram" => match value.parse() {
+                Ok(n) if n > 0 => self.ngram = n,
+                _ => return Err(invalid(String::from("expected a length of at least 1"))),
+            },
+            "rank-by" => self.ranking = value.parse().map_err(invalid)?,
+            "top" => self.top = value.parse().map_err(|_| invalid(value.to_owned()))?,
+            "window" => match value.parse() {
+                Ok(window) if window > 0 => self.window = window,
+                _ => return Err(invalid(String::from("expected a size of at least 1"))),
+            },
+            "window-scope" => self.window_scope = value.parse().map_err(invalid)?,
+            "clusters" => match value.parse() {
+                Ok(clusters) if clusters > 0 => self.clusters = clusters,
+                _ => return Err(invalid(String::from("expected at least 1 cluster"))),
+            },
+            "distance" => self.distance = value.parse().map_err(invalid)?,
+            "pattern" => self.pattern = Some(value.parse().map_err(invalid)?),
+            "threads" => self.threads = value.parse().map_err(|_| invalid(value.to_owned()))?,
+            "time-range" => self.time_range = Some(value.parse().map_err(invalid)?),
+            "ledger-range" => self.ledger_range = Some(value.parse().map_err(invalid)?),
+            "round-range" => self.round_range = Some(value.parse().map_err(invalid)?),
+            "restart-gap" => {
+                self.restart_gap = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            "max-gap" => self.max_gap = value.parse().map_err(|_| invalid(value.to_owned()))?,
+            "drop-incomplete" => {
+                self.drop_incomplete = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            "mode" => self.mode = Some(value.parse().map_err(invalid)?),
+            "synced" => self.synced = Some(value.parse().map_err(|_| invalid(value.to_owned()))?),
+            "group-by-mode" => {
+                self.group_by_mode = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            "checkpoint" => {
+                self.checkpoint = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            "resume" => self.resume = value.parse().map_err(|_| invalid(value.to_owned()))?,
+            "dedup" => self.dedup = value.parse().map_err(|_| invalid(value.to_owned()))?,
+            "raw-messages" => {
+                self.raw_messages = value.parse().map_err(|_| invalid(value.to_owned()))?
+            }
+            _ => return Err(ConfigError::UnknownKey(key.to_owned())),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn commands_read_the_whole_log_by_default() {
+        let config = Config::from_args(&args(&["clplog", "example.log"])).unwrap();
+        assert_eq!(config.amount_batches, AMOUNT_BATCHES);
+        let config = Config::from_args(&args(&["clplog", "learn", "example.log"])).unwrap();
+        assert_eq!(config.amount_batches, -1);
+        let config =
+            Config::from_args(&args(&["clplog", "learn", "--batches", "2", "example.log"]))
+                .unwrap();
+        assert_eq!(config.amount_batches, 2);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::config::Config;
+use crate::learn::Automaton;
+use crate::pipeline::Pipeline;
+use crate::round::{format_timestamp, Round};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+/// Label the automaton had no transition for, the event is skipped and the replay continues in
+/// the same state
+pub struct Deviation {
+    // Position of the event within the round
+    pub position: usize,
+    pub state: usize,
+    pub label: String,
+}
+
+/// Result of replaying one round through the reference automaton
+pub struct Replay {
+    pub deviations: Vec<Deviation>,
+    // State reached after the last event
+    pub state: usize,
+    // False when the round ended in a state that is not accepting
+    pub accepted: bool,
+    pub fitness: f64,
+}
+
+impl Replay {
+    pub fn conforms(&self) -> bool {
+        self.deviations.is_empty() && self.accepted
+    }
+}
+
+/// Replay the labels of a round, the end of the round counts as one more step that deviates
+/// when it is not accepted
+pub fn replay(automaton: &Automaton, labels: &[String], check_end: bool) -> Replay {
+    let mut state = 0;
+    let mut deviations = Vec::<Deviation>::new();
+    for (position, label) in labels.iter().enumerate() {
+        match automaton.step(state, label) {
+            Some(target) => state = target,
+            None => deviations.push(Deviation {
+                position,
+                state,
+                label: label.clone(),
+            }),
+        }
+    }
+
+    let accepted = !check_end || automaton.states[state].accepting > 0;
+    let failed = deviations.len() + !accepted as usize;
+    Replay {
+        deviations,
+        state,
+        accepted,
+        fitness: 1.0 - failed as f64 / (labels.len() + 1) as f64,
+    }
+}
+
+/// Write the deviations of a round to the report
+fn write_round<W: Write>(
+    report: &mut W,
+    item: &Round,
+    replay: &Replay,
+) -> Result<(), Box<dyn std::error::Error>> {
+    writeln!(
+        report,
+        "round {} start {} end {} fitness {:.4}",
+        item.index,
+        format_timestamp(&item.start).unwrap_or_else(|| String::from("-")),
+        format_timestamp(&item.end).unwrap_or_else(|| String::from("-")),
+        replay.fitness
+    )?;
+    for deviation in replay.deviations.iter() {
+        writeln!(
+            report,
+            "  event {} state {} unexpected {}",
+            deviation.position, deviation.state, deviation.label
+        )?;
+    }
+    if !replay.accepted {
+        writeln!(
+            report,
+            "  ended in state {} which is not accepting",
+            replay.state
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replay every round through the automaton given as `model`, deviating rounds and the fitness of
+/// every batch are written to a `.conformance` report
+pub fn conformance(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let model = config.model.as_deref().unwrap_or_default();
+    let automaton = Automaton::read(model)?;
+    // Models without accepting states only describe the transitions
+    let check_end = automaton.states.iter().any(|state| state.accepting > 0);
+
+    let mut report = BufWriter::new(File::create(format!("{}.conformance", config.filename))?);
+    let mut total_rounds = 0;
+    let mut total_deviating = 0;
+    let mut total_fitness = 0.0;
+
+    let mut pipeline = Pipeline::new();
+    pipeline.run(config, |batch, log_list| {
+        let mut deviating = 0;
+        let mut fitness = 0.0;
+        for item in batch.rounds.iter() {
+            let labels = item.labels(log_list);
+            let replay = replay(&automaton, &labels, check_end);
+            fitness += replay.fitness;
+            if !replay.conforms() {
+                deviating += 1;
+                write_round(&mut report, item, &replay)?;
+            }
+        }
+
+        let rounds = batch.rounds.len();
+        let line = format!(
+            "batch {} rounds {} deviating {} fitness {:.4}",
+            batch.name,
+            rounds,
+            deviating,
+            fitness / rounds.max(1) as f64
+        );
+        writeln!(report, "{}", line)?;
+        println!("{}", line);
+
+        total_rounds += rounds;
+        total_deviating += deviating;
+        total_fitness += fitness;
+        Ok(())
+    })?;
+
+    let line = format!(
+        "total rounds {} deviating {} fitness {:.4}",
+        total_rounds,
+        total_deviating,
+        total_fitness / total_rounds.max(1) as f64
+    );
+    writeln!(report, "{}", line)?;
+    println!("{}", line);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::round::{format_timestamp, Round};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+/// Group rounds with the same label and sequence, in order of their first occurrence
+pub fn unique_traces(all_log_sequence: &[Round]) -> Vec<Vec<&Round>> {
+    let mut traces = Vec::<Vec<&Round>>::new();
+    // (label, (log_id, count) per event) -> index into traces
+    let mut trace_ids = HashMap::<(u8, Vec<(u64, u64)>), usize>::new();
+
+    for item in all_log_sequence.iter() {
+        let key = (
+            item.label,
+            item.events
+                .iter()
+                .map(|event| (event.log_id, event.count))
+                .collect(),
+        );
+        match trace_ids.get(&key) {
+            Some(trace) => traces[*trace].push(item),
+            None => {
+                trace_ids.insert(key, traces.len());
+                traces.push(vec![item]);
+            }
+        }
+    }
+
+    traces
+}
+
+/// Write the .traces file, one line per unique trace with its line in the .parsed file, its
+/// occurrence count and the index and start of every round it stands for
+pub fn write_traces(
+    filename: &str,
+    traces: &[Vec<&Round>],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let traces_file = File::create(filename.to_owned() + ".traces")?;
+    let mut traces_file = BufWriter::new(traces_file);
+
+    for (trace, rounds) in traces.iter().enumerate() {
+        write!(traces_file, "{} {}", trace, rounds.len())?;
+        for item in rounds.iter() {
+            write!(
+                traces_file,
+                " {}@{}",
+                item.index,
+                format_timestamp(&item.start).unwrap_or_else(|| String::from("-"))
+            )?;
+        }
+        writeln!(traces_file)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use crate::config::Config;
+use crate::round::Round;
+use crate::session::BACKWARD_TOLERANCE_SECONDS;
+use chrono::{Duration, NaiveDateTime};
+use regex::Regex;
+use std::str::FromStr;
+
+// Formats accepted for the bounds of a time range, from most to least precise
+static TIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"];
+
+lazy_static! {
+    // Messages naming the sequence of the ledger a round works on
+    static ref LEDGER_SEQUENCE: Regex =
+        Regex::new(r"(?:Built ledger #|Advancing accepted ledger to )(\d+)").unwrap();
+}
+
+/// Range of values with optional bounds, both included, written as `<from>..<to>` where either
+/// side may be left empty
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval<T> {
+    pub from: Option<T>,
+    pub to: Option<T>,
+}
+
+impl<T: PartialOrd> Interval<T> {
+    /// Parse the bounds with `parse_bound`, an empty side leaves the range open
+    fn parse<F>(s: &str, parse_bound: F) -> Result<Interval<T>, String>
+    where
+        F: Fn(&str) -> Result<T, String>,
+    {
+        let pos = match s.find("..") {
+            Some(pos) => pos,
+            None => return Err(format!("expected <from>..<to>, got \"{}\"", s)),
+        };
+        let bound = |bound: &str| -> Result<Option<T>, String> {
+            match bound.trim() {
+                "" => Ok(None),
+                bound => parse_bound(bound).map(Some),
+            }
+        };
+        let interval = Interval {
+            from: bound(&s[..pos])?,
+            to: bound(&s[pos + 2..])?,
+        };
+
+        if let (Some(from), Some(to)) = (&interval.from, &interval.to) {
+            if from > to {
+                return Err(format!("empty range \"{}\"", s));
+            }
+        }
+        Ok(interval)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let before = matches!(&self.from, Some(from) if value < from);
+        !before && !self.after(value)
+    }
+
+    /// True for values beyond the range
+    pub fn after(&self, value: &T) -> bool {
+        matches!(&self.to, Some(to) if value > to)
+    }
+}
+
+impl FromStr for Interval<u64> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Interval<u64>, String> {
+        Interval::parse(s, |bound| {
+            // Ledger sequences are often written with thousands separators (e.g.: 5,300,000)
+            bound
+                .replace(',', "")
+                .parse()
+                .map_err(|_| format!("invalid number \"{}\"", bound))
+        })
+    }
+}
+
+impl FromStr for Interval<NaiveDateTime> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Interval<NaiveDateTime>, String> {
+        Interval::parse(s, parse_time)
+    }
+}
+
+/// Parse a timestamp given in the config (e.g.: 2020-03-05T14:00 or 2020-03-05T14:00:30.5)
+pub fn parse_time(s: &str) -> Result<NaiveDateTime, String> {
+    TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .ok_or_else(|| format!("invalid time \"{}\", expected YYYY-MM-DDTHH:MM[:SS]", s))
+}
+
+/// Sequence of the ledger named by a message, if any
+pub fn parse_ledger(msg: &str) -> Option<u64> {
+    LEDGER_SEQUENCE
+        .captures(msg)
+        .and_then(|captures| captures[1].parse().ok())
+}
+
+/// Whether the start and index of a new round lie within the configured `time-range` and
+/// `round-range` and it has the configured `mode` and `synced` state, rounds without a timestamp
+/// or without these attributes are left out by the respective filter
+pub fn starts_in_range(round: &Round, config: &Config) -> bool {
+    if config.mode.is_some() && round.mode != config.mode {
+        return false;
+    }
+    if config.synced.is_some() && round.synced != config.synced {
+        return false;
+    }
+    if let Some(range) = &config.time_range {
+        if !round.start.is_some_and(|start| range.contains(&start)) {
+            return false;
+        }
+    }
+    if let Some(range) = &config.round_range {
+        if !range.contains(&(round.index as u64)) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Whether a line is too old to belong to a round starting within the configured `time-range`, a
+/// kept round only takes lines that go back less than the tolerance of a restart
+pub fn before_range(timestamp: Option<NaiveDateTime>, config: &Config) -> bool {
+    match (config.time_range.and_then(|range| range.from), timestamp) {
+        (Some(from), Some(timestamp)) => {
+            timestamp < from - Duration::seconds(BACKWARD_TOLERANCE_SECONDS)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a finished round worked on a ledger within the configured `ledger-range`, rounds
+/// without a ledger sequence are left out
+pub fn ledger_in_range(round: &Round, config: &Config) -> bool {
+    match &config.ledger_range {
+        Some(range) => round.ledger.is_some_and(|ledger| range.contains(&ledger)),
+        None => true,
+    }
+}
+
+/// Whether the round lies beyond one of the configured ranges, so none of the following rounds can
+/// pass either and the rest of the log is skipped
+pub fn past_range(round: &Round, config: &Config) -> bool {
+    let time = match (&config.time_range, round.start) {
+        (Some(range), Some(start)) => range.after(&start),
+        _ => false,
+    };
+    let ledger = match (&config.ledger_range, round.ledger) {
+        (Some(range), Some(ledger)) => range.after(&ledger),
+        _ => false,
+    };
+    let index = match &config.round_range {
+        Some(range) => range.after(&(round.index as u64)),
+        None => false,
+    };
+
+    time || ledger || index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ledger_interval() {
+        assert_eq!(
+            "5,300,000..5,300,500".parse(),
+            Ok(Interval {
+                from: Some(5300000u64),
+                to: Some(5300500)
+            })
+        );
+        assert_eq!(
+            "..10".parse(),
+            Ok(Interval {
+                from: None,
+                to: Some(10u64)
+            })
+        );
+        assert_eq!(
+            "10..".parse(),
+            Ok(Interval {
+                from: Some(10u64),
+                to: None
+            })
+        );
+        assert!("5..3".parse::<Interval<u64>>().is_err());
+        assert!("5".parse::<Interval<u64>>().is_err());
+        assert!("a..b".parse::<Interval<u64>>().is_err());
+    }
+
+    #[test]
+    fn parse_time_interval() {
+        let interval = "2020-03-05T14:00..2020-03-05T14:30:00.5"
+            .parse::<Interval<NaiveDateTime>>()
+            .unwrap();
+        assert_eq!(interval.from, parse_time("2020-03-05T14:00:00").ok());
+        assert_eq!(
+            interval.to.map(|to| to.format("%H:%M:%S%.3f").to_string()),
+            Some(String::from("14:30:00.500"))
+        );
+        assert!("2020-03-05 14:00.."
+            .parse::<Interval<NaiveDateTime>>()
+            .is_err());
+    }
+
+    #[test]
+    fn bounds_are_included() {
+        let interval = "3..5".parse::<Interval<u64>>().unwrap();
+        assert!(!interval.contains(&2));
+        assert!(interval.contains(&3));
+        assert!(interval.contains(&5));
+        assert!(!interval.contains(&6));
+        assert!(!interval.after(&5));
+        assert!(interval.after(&6));
+
+        let open = "..".parse::<Interval<u64>>().unwrap();
+        assert!(open.contains(&0));
+        assert!(!open.after(&u64::MAX));
+    }
+
+    #[test]
+    fn ledger_sequence_of_message() {
+        assert_eq!(
+            parse_ledger("Built ledger #5300000: 3176813E"),
+            Some(5300000)
+        );
+        assert_eq!(
+            parse_ledger("Advancing accepted ledger to 5300001 with >= 4 validations"),
+            Some(5300001)
+        );
+        assert_eq!(parse_ledger("Consensus built new ledger"), None);
+    }
+
+    #[test]
+    fn lines_before_the_time_range() {
+        let mut config = Config::default();
+        let time = |s: &str| parse_time(s).ok();
+        assert!(!before_range(time("2020-03-05T13:00"), &config));
+
+        config.time_range = "2020-03-05T14:00..".parse().ok();
+        assert!(before_range(time("2020-03-05T13:59:58.9"), &config));
+        // Lines may still go back into a round starting at the bound without a restart
+        assert!(!before_range(time("2020-03-05T13:59:59"), &config));
+        assert!(!before_range(time("2020-03-05T14:00"), &config));
+        assert!(!before_range(None, &config));
+    }
+}
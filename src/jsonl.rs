@@ -0,0 +1,66 @@
+use crate::compress::Compression;
+use crate::config::Config;
+use crate::map_log;
+use crate::round::{format_timestamp, Round};
+use serde_json::json;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+/// Write one JSON object per round with all event details to a .jsonl file
+pub fn write_jsonl(
+    filename: &str,
+    all_log_sequence: &[Round],
+    log_list: &[String],
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let jsonl_file = File::create(filename.to_owned() + ".jsonl")?;
+    let mut jsonl_file = BufWriter::new(jsonl_file);
+
+    for item in all_log_sequence.iter() {
+        let events = item
+            .events
+            .iter()
+            .map(|event| {
+                let mut object = json!({
+                    "timestamp": format_timestamp(&event.timestamp),
+                    "partition": event.partition,
+                    "severity": event.severity,
+                    "template_id": event.log_id,
+                    "label": map_log(&event.log_id, log_list, false),
+                    "template": log_list[event.log_id as usize],
+                });
+                if config.compression == Compression::RunLength {
+                    object["count"] = json!(event.count);
+                }
+                if let Some(raw) = &event.raw {
+                    object["raw"] = json!(raw);
+                }
+                object
+            })
+            .collect::<Vec<serde_json::Value>>();
+
+        let mut round = json!({
+            "round": item.index,
+            "node": config.node,
+            "session": item.session,
+            "start": format_timestamp(&item.start),
+            "end": format_timestamp(&item.end),
+            "label": item.label,
+            "status": item.status.to_string(),
+            "events": events,
+        });
+        if let Some(ledger) = item.ledger {
+            round["ledger"] = json!(ledger);
+        }
+        if let Some(mode) = item.mode {
+            round["mode"] = json!(mode.to_string());
+        }
+        if let Some(synced) = item.synced {
+            round["synced"] = json!(synced);
+        }
+        writeln!(jsonl_file, "{}", round)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,891 @@
+use crate::config::Config;
+use crate::pipeline::Pipeline;
+use regex::Regex;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::str::FromStr;
+
+// Marks the end of a trace in the k-tails of a state
+static END: usize = usize::MAX;
+
+/// Change made by a merge, with what it replaced
+enum Change {
+    // Counts of a state before the folded state was added to them
+    Counts(usize, u64, u64, u64),
+    // Count of the transition of a state on a symbol
+    TransitionCount(usize, usize, u64),
+    // Target of the transition of a state on a symbol
+    Target(usize, usize, usize),
+    // Transition added to a state on a symbol
+    Inserted(usize, usize),
+    // State folded into another one
+    Folded(usize, State),
+}
+
+impl Change {
+    fn state(&self) -> usize {
+        match self {
+            Change::Counts(state, ..) => *state,
+            Change::TransitionCount(state, ..) => *state,
+            Change::Target(state, ..) => *state,
+            Change::Inserted(state, _) => *state,
+            Change::Folded(state, _) => *state,
+        }
+    }
+}
+
+/// Changes of a merge, undone in reverse order
+type Undo = Vec<Change>;
+
+/// State merging strategy applied to the prefix tree acceptor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Learner {
+    // Merge states whose futures up to length k are identical
+    KTails(usize),
+    // Evidence driven state merging, merges need at least this score
+    Edsm(u64),
+}
+
+impl Default for Learner {
+    fn default() -> Learner {
+        Learner::Edsm(1)
+    }
+}
+
+impl FromStr for Learner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Learner, String> {
+        let (name, parameter) = match s.find(':') {
+            Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+            None => (s, None),
+        };
+        let parameter = match parameter.map(|parameter| parameter.parse::<u64>()) {
+            Some(Ok(parameter)) => Some(parameter),
+            Some(Err(_)) => return Err(format!("invalid parameter in \"{}\"", s)),
+            None => None,
+        };
+
+        match name {
+            "ktails" => Ok(Learner::KTails(parameter.unwrap_or(2) as usize)),
+            "edsm" => Ok(Learner::Edsm(parameter.unwrap_or(1))),
+            _ => Err(format!(
+                "unknown learner \"{}\", expected ktails[:<k>] or edsm[:<min-score>]",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Transition {
+    pub target: usize,
+    // Amount of traces taking this transition
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct State {
+    // Symbol -> transition, ordered so the output is stable
+    pub transitions: BTreeMap<usize, Transition>,
+    // Amount of traces passing through this state
+    pub count: u64,
+    // Amount of positive traces ending in this state
+    pub accepting: u64,
+    // Amount of negative traces ending in this state
+    pub rejecting: u64,
+}
+
+/// K-tails of the red states, kept up to date after every merge instead of computed for every pair
+#[derive(Default)]
+struct RedTails {
+    // Tails per position in the list of red states
+    tails: Vec<BTreeSet<Vec<usize>>>,
+    // Tails -> positions of the red states having them
+    positions: HashMap<BTreeSet<Vec<usize>>, BTreeSet<usize>>,
+}
+
+impl RedTails {
+    fn set(&mut self, position: usize, tails: BTreeSet<Vec<usize>>) {
+        if position < self.tails.len() {
+            let previous = &self.tails[position];
+            if *previous == tails {
+                return;
+            }
+            let positions = self.positions.get_mut(previous).unwrap();
+            positions.remove(&position);
+            if positions.is_empty() {
+                self.positions.remove(previous);
+            }
+            self.tails[position] = tails.clone();
+        } else {
+            self.tails.push(tails.clone());
+        }
+        self.positions.entry(tails).or_default().insert(position);
+    }
+
+    /// Positions of the red states with these tails, in the order they turned red
+    fn positions(&self, tails: &BTreeSet<Vec<usize>>) -> Vec<usize> {
+        match self.positions.get(tails) {
+            Some(positions) => positions.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Deterministic automaton over labels, state 0 is the initial state
+#[derive(Clone, Debug, Default)]
+pub struct Automaton {
+    pub alphabet: Vec<String>,
+    pub states: Vec<State>,
+}
+
+impl Automaton {
+    /// Build the prefix tree acceptor of the traces, every trace is a sequence of symbols and its
+    /// Abbadingo label
+    pub fn prefix_tree(alphabet: Vec<String>, traces: &[(Vec<usize>, u8)]) -> Automaton {
+        let mut automaton = Automaton {
+            alphabet,
+            states: vec![State::default()],
+        };
+
+        for (trace, label) in traces.iter() {
+            let mut state = 0;
+            automaton.states[state].count += 1;
+            for symbol in trace.iter() {
+                let next = match automaton.states[state].transitions.get(symbol) {
+                    Some(transition) => transition.target,
+                    None => {
+                        automaton.states.push(State::default());
+                        let next = automaton.states.len() - 1;
+                        automaton.states[state].transitions.insert(
+                            *symbol,
+                            Transition {
+                                target: next,
+                                count: 0,
+                            },
+                        );
+                        next
+                    }
+                };
+                automaton.states[state]
+                    .transitions
+                    .get_mut(symbol)
+                    .unwrap()
+                    .count += 1;
+                state = next;
+                automaton.states[state].count += 1;
+            }
+
+            if *label == 0 {
+                automaton.states[state].rejecting += 1;
+            } else {
+                automaton.states[state].accepting += 1;
+            }
+        }
+
+        automaton
+    }
+
+    /// Fold the tree rooted at `blue` into `red`, returns the evidence score or None when an
+    /// accepting and rejecting state would be merged
+    fn fold(&mut self, red: usize, blue: usize, undo: &mut Undo) -> Option<u64> {
+        let blue_state = std::mem::take(&mut self.states[blue]);
+        let score = self.fold_state(red, &blue_state, undo);
+        undo.push(Change::Folded(blue, blue_state));
+        score
+    }
+
+    /// Add the counts and transitions of a folded state to `red`
+    fn fold_state(&mut self, red: usize, blue_state: &State, undo: &mut Undo) -> Option<u64> {
+        let red_state = &mut self.states[red];
+        undo.push(Change::Counts(
+            red,
+            red_state.count,
+            red_state.accepting,
+            red_state.rejecting,
+        ));
+
+        let mut score = 0;
+        if red_state.accepting > 0 && blue_state.accepting > 0 {
+            score += 1;
+        }
+        if red_state.rejecting > 0 && blue_state.rejecting > 0 {
+            score += 1;
+        }
+        red_state.count += blue_state.count;
+        red_state.accepting += blue_state.accepting;
+        red_state.rejecting += blue_state.rejecting;
+        if red_state.accepting > 0 && red_state.rejecting > 0 {
+            return None;
+        }
+
+        for (symbol, transition) in blue_state.transitions.iter() {
+            match self.states[red].transitions.get_mut(symbol) {
+                Some(existing) => {
+                    undo.push(Change::TransitionCount(red, *symbol, existing.count));
+                    existing.count += transition.count;
+                    let target = existing.target;
+                    score += self.fold(target, transition.target, undo)?;
+                }
+                None => {
+                    undo.push(Change::Inserted(red, *symbol));
+                    self.states[red]
+                        .transitions
+                        .insert(*symbol, transition.clone());
+                }
+            }
+        }
+
+        Some(score)
+    }
+
+    /// Merge blue state into red state by redirecting the transition pointing to blue, the only one
+    /// as blue is still the root of a tree; `parent` is the state and symbol of that transition
+    fn merge(
+        &mut self,
+        red: usize,
+        blue: usize,
+        parent: (usize, usize),
+        undo: &mut Undo,
+    ) -> Option<u64> {
+        let (state, symbol) = parent;
+        let transition = self.states[state].transitions.get_mut(&symbol).unwrap();
+        undo.push(Change::Target(state, symbol, transition.target));
+        transition.target = red;
+
+        self.fold(red, blue, undo)
+    }
+
+    /// Restore the states changed by a trial merge
+    fn undo(&mut self, undo: Undo) {
+        for change in undo.into_iter().rev() {
+            match change {
+                Change::Counts(state, count, accepting, rejecting) => {
+                    self.states[state].count = count;
+                    self.states[state].accepting = accepting;
+                    self.states[state].rejecting = rejecting;
+                }
+                Change::TransitionCount(state, symbol, count) => {
+                    self.states[state]
+                        .transitions
+                        .get_mut(&symbol)
+                        .unwrap()
+                        .count = count;
+                }
+                Change::Target(state, symbol, target) => {
+                    self.states[state]
+                        .transitions
+                        .get_mut(&symbol)
+                        .unwrap()
+                        .target = target;
+                }
+                Change::Inserted(state, symbol) => {
+                    self.states[state].transitions.remove(&symbol);
+                }
+                Change::Folded(state, content) => self.states[state] = content,
+            }
+        }
+    }
+
+    /// Sequences of at most k symbols readable from the state, ending early in accepting states
+    fn tails(&self, state: usize, k: usize) -> BTreeSet<Vec<usize>> {
+        let mut tails = BTreeSet::new();
+        if self.states[state].accepting > 0 {
+            tails.insert(vec![END]);
+        }
+        if k == 0 || self.states[state].transitions.is_empty() {
+            tails.insert(Vec::new());
+            return tails;
+        }
+
+        for (symbol, transition) in self.states[state].transitions.iter() {
+            for tail in self.tails(transition.target, k - 1).into_iter() {
+                let mut sequence = vec![*symbol];
+                sequence.extend(tail);
+                tails.insert(sequence);
+            }
+        }
+
+        tails
+    }
+
+    /// Whether one of the `changed` states is reachable from the state in at most k steps, only
+    /// then its k-tails may have changed
+    fn reaches(&self, state: usize, k: usize, changed: &HashSet<usize>) -> bool {
+        if changed.contains(&state) {
+            return true;
+        }
+        if k == 0 {
+            return false;
+        }
+        self.states[state]
+            .transitions
+            .values()
+            .any(|transition| self.reaches(transition.target, k - 1, changed))
+    }
+
+    /// Generalize the prefix tree with the blue-fringe framework, blue states are either merged
+    /// with a red state or promoted to red themselves
+    pub fn learn(&mut self, learner: Learner) {
+        let mut red = vec![0];
+        let mut red_tails = RedTails::default();
+        // K-tails of the blue states looked at, dropped once a merge may change them
+        let mut blue_tails = HashMap::<usize, BTreeSet<Vec<usize>>>::new();
+        if let Learner::KTails(k) = learner {
+            red_tails.set(0, self.tails(0, k));
+        }
+
+        loop {
+            let red_set = red.iter().cloned().collect::<BTreeSet<usize>>();
+            // Blue state -> state and symbol of the transition leading to it
+            let mut blue = BTreeMap::<usize, (usize, usize)>::new();
+            for state in red.iter() {
+                for (symbol, transition) in self.states[*state].transitions.iter() {
+                    if !red_set.contains(&transition.target) {
+                        blue.insert(transition.target, (*state, *symbol));
+                    }
+                }
+            }
+            if blue.is_empty() {
+                break;
+            }
+            // Best merge per blue state, the first one that cannot be merged with any red state is
+            // promoted instead
+            let mut best = Vec::<(usize, u64, usize)>::new();
+            let mut promoted = None;
+            for (b, parent) in blue.iter() {
+                // K-tails only merges with the red states having the same tails
+                let candidates = match learner {
+                    Learner::KTails(k) => red_tails
+                        .positions(blue_tails.entry(*b).or_insert_with(|| self.tails(*b, k)))
+                        .into_iter()
+                        .map(|position| red[position])
+                        .collect(),
+                    Learner::Edsm(_) => red.clone(),
+                };
+                let mut best_merge: Option<(u64, usize)> = None;
+                for r in candidates.iter() {
+                    // Merged in place and undone, copying the automaton for every pair is too slow
+                    let mut undo = Undo::new();
+                    let merged = self.merge(*r, *b, *parent, &mut undo);
+                    self.undo(undo);
+                    if let Some(score) = merged {
+                        let better = match best_merge {
+                            Some((best_score, _)) => score > best_score,
+                            None => true,
+                        };
+                        if better {
+                            best_merge = Some((score, *r));
+                        }
+                        if let Learner::KTails(_) = learner {
+                            break;
+                        }
+                    }
+                }
+
+                if let (Learner::Edsm(min_score), Some((score, _))) = (learner, best_merge) {
+                    if score < min_score {
+                        best_merge = None;
+                    }
+                }
+                match best_merge {
+                    Some((score, r)) => best.push((*b, score, r)),
+                    None => {
+                        promoted = Some(*b);
+                        break;
+                    }
+                }
+            }
+
+            match promoted {
+                Some(b) => {
+                    red.push(b);
+                    if let Learner::KTails(k) = learner {
+                        let tails = blue_tails.remove(&b).unwrap_or_else(|| self.tails(b, k));
+                        red_tails.set(red.len() - 1, tails);
+                    }
+                }
+                None => {
+                    let (b, _, r) = best
+                        .iter()
+                        .max_by_key(|(b, score, _)| (*score, std::cmp::Reverse(*b)))
+                        .unwrap();
+                    let mut undo = Undo::new();
+                    self.merge(*r, *b, blue[b], &mut undo);
+                    if let Learner::KTails(k) = learner {
+                        let changed = undo.iter().map(|change| change.state()).collect();
+                        for (position, state) in red.iter().enumerate() {
+                            if self.reaches(*state, k, &changed) {
+                                red_tails.set(position, self.tails(*state, k));
+                            }
+                        }
+                        blue_tails.retain(|state, _| !self.reaches(*state, k, &changed));
+                    }
+                }
+            }
+        }
+
+        self.compact();
+    }
+
+    /// Drop unreachable states and number the remaining ones in breadth-first order
+    fn compact(&mut self) {
+        let mut numbering = HashMap::<usize, usize>::new();
+        let mut order = Vec::<usize>::new();
+        let mut queue = VecDeque::new();
+        numbering.insert(0, 0);
+        queue.push_back(0);
+        while let Some(state) = queue.pop_front() {
+            order.push(state);
+            for transition in self.states[state].transitions.values() {
+                if !numbering.contains_key(&transition.target) {
+                    numbering.insert(transition.target, numbering.len());
+                    queue.push_back(transition.target);
+                }
+            }
+        }
+
+        let mut states = order
+            .iter()
+            .map(|state| self.states[*state].clone())
+            .collect::<Vec<State>>();
+        for state in states.iter_mut() {
+            for transition in state.transitions.values_mut() {
+                transition.target = numbering[&transition.target];
+            }
+        }
+
+        self.states = states;
+    }
+
+    pub fn transition_count(&self) -> usize {
+        self.states
+            .iter()
+            .map(|state| state.transitions.len())
+            .sum()
+    }
+
+    pub fn write_dot(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let dot_file = File::create(filename)?;
+        let mut dot_file = BufWriter::new(dot_file);
+
+        writeln!(dot_file, "digraph automaton {{")?;
+        writeln!(dot_file, "    rankdir=LR;")?;
+        writeln!(dot_file, "    __start [shape=point];")?;
+        writeln!(dot_file, "    __start -> 0;")?;
+        for (id, state) in self.states.iter().enumerate() {
+            let shape = if state.accepting > 0 {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            let color = if state.rejecting > 0 { "red" } else { "black" };
+            writeln!(
+                dot_file,
+                "    {} [shape={} color={} label=\"{}\\n{}\"];",
+                id, shape, color, id, state.count
+            )?;
+        }
+        for (id, state) in self.states.iter().enumerate() {
+            for (symbol, transition) in state.transitions.iter() {
+                writeln!(
+                    dot_file,
+                    "    {} -> {} [label=\"{}\" weight={}];",
+                    id, transition.target, self.alphabet[*symbol], transition.count
+                )?;
+            }
+        }
+        writeln!(dot_file, "}}")?;
+
+        Ok(())
+    }
+
+    pub fn write_json(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let states = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(id, state)| {
+                json!({
+                    "id": id,
+                    "count": state.count,
+                    "accepting": state.accepting,
+                    "rejecting": state.rejecting,
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+        let transitions = self
+            .states
+            .iter()
+            .enumerate()
+            .flat_map(|(id, state)| {
+                state.transitions.iter().map(move |(symbol, transition)| {
+                    json!({
+                        "from": id,
+                        "to": transition.target,
+                        "label": self.alphabet[*symbol],
+                        "count": transition.count,
+                    })
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+
+        let json_file = File::create(filename)?;
+        let mut json_file = BufWriter::new(json_file);
+        serde_json::to_writer_pretty(
+            &mut json_file,
+            &json!({
+                "initial": 0,
+                "states": states,
+                "transitions": transitions,
+            }),
+        )?;
+        writeln!(json_file)?;
+
+        Ok(())
+    }
+
+    /// Build an automaton from the states and labeled transitions of a model file, the initial
+    /// state becomes state 0 and for duplicate transitions the first one is kept
+    fn from_parts(
+        initial: &str,
+        states: &[(String, State)],
+        transitions: &[(String, String, String, u64)],
+    ) -> Automaton {
+        let mut automaton = Automaton::default();
+        let mut numbering = HashMap::<String, usize>::new();
+        let mut symbols = HashMap::<String, usize>::new();
+
+        let ids = std::iter::once(initial)
+            .chain(states.iter().map(|(id, _)| id.as_str()))
+            .chain(
+                transitions
+                    .iter()
+                    .flat_map(|(from, to, _, _)| vec![from.as_str(), to.as_str()]),
+            );
+        for id in ids {
+            if !numbering.contains_key(id) {
+                numbering.insert(id.to_owned(), automaton.states.len());
+                automaton.states.push(State::default());
+            }
+        }
+
+        for (id, state) in states.iter() {
+            automaton.states[numbering[id]] = state.clone();
+        }
+        for (from, to, label, count) in transitions.iter() {
+            let symbol = *symbols.entry(label.clone()).or_insert_with(|| {
+                automaton.alphabet.push(label.clone());
+                automaton.alphabet.len() - 1
+            });
+            automaton.states[numbering[from]]
+                .transitions
+                .entry(symbol)
+                .or_insert(Transition {
+                    target: numbering[to],
+                    count: *count,
+                });
+        }
+
+        automaton
+    }
+
+    /// Read an automaton as written by `write_json`
+    pub fn read_json(filename: &str) -> Result<Automaton, Box<dyn std::error::Error>> {
+        let file = File::open(filename)?;
+        let value: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+        let invalid = || format!("invalid automaton in {}", filename);
+        // State ids may be numbers or strings
+        let id = |value: &serde_json::Value| match value {
+            serde_json::Value::String(id) => id.clone(),
+            other => other.to_string(),
+        };
+        let number = |value: &serde_json::Value| match value {
+            serde_json::Value::Bool(flag) => *flag as u64,
+            other => other.as_u64().unwrap_or(0),
+        };
+
+        let states = value["states"]
+            .as_array()
+            .map(|states| {
+                states
+                    .iter()
+                    .map(|state| {
+                        (
+                            id(&state["id"]),
+                            State {
+                                transitions: BTreeMap::new(),
+                                count: number(&state["count"]),
+                                accepting: number(&state["accepting"]),
+                                rejecting: number(&state["rejecting"]),
+                            },
+                        )
+                    })
+                    .collect::<Vec<(String, State)>>()
+            })
+            .unwrap_or_default();
+        let transitions = value["transitions"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|transition| match transition["label"].as_str() {
+                Some(label) => Ok((
+                    id(&transition["from"]),
+                    id(&transition["to"]),
+                    label.to_owned(),
+                    number(&transition["count"]),
+                )),
+                None => Err(invalid()),
+            })
+            .collect::<Result<Vec<(String, String, String, u64)>, String>>()?;
+        let initial = match &value["initial"] {
+            serde_json::Value::Null => String::from("0"),
+            initial => id(initial),
+        };
+
+        Ok(Automaton::from_parts(&initial, &states, &transitions))
+    }
+
+    /// Read an automaton from a DOT graph with transitions named after labels, accepting states
+    /// are drawn as doublecircle and the initial state is pointed to by a point shaped node
+    pub fn read_dot(filename: &str) -> Result<Automaton, Box<dyn std::error::Error>> {
+        lazy_static! {
+            static ref RE_EDGE: Regex =
+                Regex::new(r#"^\s*"?([^\s"\[;]+)"?\s*->\s*"?([^\s"\[;]+)"?\s*(\[(.*)\])?"#)
+                    .unwrap();
+            static ref RE_NODE: Regex = Regex::new(r#"^\s*"?([^\s"\[;]+)"?\s*\[(.*)\]"#).unwrap();
+            static ref RE_LABEL: Regex = Regex::new(r#"label\s*=\s*"([^"]*)""#).unwrap();
+        }
+
+        let file = File::open(filename)?;
+        let mut states = Vec::<(String, State)>::new();
+        let mut transitions = Vec::<(String, String, String, u64)>::new();
+        let mut start_nodes = Vec::<String>::new();
+        let mut initial = None;
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if let Some(edge) = RE_EDGE.captures(&line) {
+                let from = edge.get(1).unwrap().as_str().to_owned();
+                let to = edge.get(2).unwrap().as_str().to_owned();
+                if start_nodes.contains(&from) {
+                    initial = Some(to);
+                    continue;
+                }
+                let attributes = edge
+                    .get(4)
+                    .map(|attributes| attributes.as_str())
+                    .unwrap_or("");
+                if let Some(label) = RE_LABEL.captures(attributes) {
+                    transitions.push((from, to, label.get(1).unwrap().as_str().to_owned(), 0));
+                }
+            } else if let Some(node) = RE_NODE.captures(&line) {
+                let id = node.get(1).unwrap().as_str().to_owned();
+                let attributes = node.get(2).unwrap().as_str();
+                if attributes.contains("shape=point") {
+                    start_nodes.push(id);
+                } else if id != "node" && id != "edge" && id != "graph" {
+                    let state = State {
+                        accepting: attributes.contains("doublecircle") as u64,
+                        ..State::default()
+                    };
+                    states.push((id, state));
+                }
+            }
+        }
+
+        let initial = initial.unwrap_or_else(|| String::from("0"));
+        Ok(Automaton::from_parts(&initial, &states, &transitions))
+    }
+
+    /// Read a .json or .dot automaton, depending on the extension
+    pub fn read(filename: &str) -> Result<Automaton, Box<dyn std::error::Error>> {
+        if filename.ends_with(".dot") || filename.ends_with(".gv") {
+            Automaton::read_dot(filename)
+        } else {
+            Automaton::read_json(filename)
+        }
+    }
+
+    /// State reached by taking the transition with the given label, if there is one
+    pub fn step(&self, state: usize, label: &str) -> Option<usize> {
+        let symbol = self.alphabet.iter().position(|symbol| symbol == label)?;
+        self.states[state]
+            .transitions
+            .get(&symbol)
+            .map(|transition| transition.target)
+    }
+}
+
+/// Learn an automaton from the label sequences of the rounds, written as .automaton.dot and
+/// .automaton.json
+pub fn learn(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut alphabet = Vec::<String>::new();
+    let mut symbols = HashMap::<String, usize>::new();
+    let mut traces = Vec::<(Vec<usize>, u8)>::new();
+
+    let mut pipeline = Pipeline::new();
+    pipeline.run(config, |batch, log_list| {
+        for item in batch.rounds.iter() {
+            let trace = item
+                .labels(log_list)
+                .into_iter()
+                .map(|label| {
+                    *symbols.entry(label.clone()).or_insert_with(|| {
+                        alphabet.push(label);
+                        alphabet.len() - 1
+                    })
+                })
+                .collect::<Vec<usize>>();
+            traces.push((trace, item.label));
+        }
+
+        Ok(())
+    })?;
+
+    let mut automaton = Automaton::prefix_tree(alphabet, &traces);
+    let prefix_tree_size = automaton.states.len();
+    automaton.learn(config.learner);
+    println!(
+        "learned automaton with {} states and {} transitions from a prefix tree of {} states",
+        automaton.states.len(),
+        automaton.transition_count(),
+        prefix_tree_size
+    );
+
+    automaton.write_dot(&format!("{}.automaton.dot", config.filename))?;
+    automaton.write_json(&format!("{}.automaton.json", config.filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn automaton(traces: &[(&[usize], u8)], learner: Learner) -> Automaton {
+        let traces = traces
+            .iter()
+            .map(|(trace, label)| (trace.to_vec(), *label))
+            .collect::<Vec<(Vec<usize>, u8)>>();
+        let mut automaton = Automaton::prefix_tree(vec![String::from("a")], &traces);
+        automaton.learn(learner);
+        automaton
+    }
+
+    #[test]
+    fn prefix_tree_counts_traces() {
+        let traces = vec![(vec![0, 0], 1), (vec![0], 0), (vec![0, 0], 1)];
+        let automaton = Automaton::prefix_tree(vec![String::from("a")], &traces);
+        assert_eq!(automaton.states.len(), 3);
+        assert_eq!(automaton.states[0].count, 3);
+        assert_eq!(automaton.states[1].rejecting, 1);
+        assert_eq!(automaton.states[2].accepting, 2);
+        assert_eq!(automaton.states[0].transitions[&0].count, 3);
+    }
+
+    #[test]
+    fn edsm_merges_repetitions_into_a_loop() {
+        let automaton = automaton(
+            &[(&[], 1), (&[0], 1), (&[0, 0], 1), (&[0, 0, 0], 1)],
+            Learner::Edsm(1),
+        );
+        assert_eq!(automaton.states.len(), 1);
+        assert_eq!(automaton.states[0].transitions[&0].target, 0);
+        assert_eq!(automaton.states[0].accepting, 4);
+        // Every visit of the merged states counts, 4 + 3 + 2 + 1
+        assert_eq!(automaton.states[0].count, 10);
+        assert_eq!(automaton.states[0].transitions[&0].count, 6);
+    }
+
+    #[test]
+    fn edsm_keeps_accepting_and_rejecting_states_apart() {
+        let automaton = automaton(&[(&[0], 1), (&[0, 0], 0)], Learner::Edsm(1));
+        assert_eq!(automaton.states.len(), 3);
+        let first = automaton.step(0, "a").unwrap();
+        let second = automaton.step(first, "a").unwrap();
+        assert_eq!(automaton.states[first].accepting, 1);
+        assert_eq!(automaton.states[second].rejecting, 1);
+        assert_eq!(automaton.step(second, "a"), None);
+    }
+
+    #[test]
+    fn ktails_merges_states_with_the_same_future() {
+        let automaton = automaton(
+            &[(&[], 1), (&[0], 1), (&[0, 0], 1), (&[0, 0, 0], 1)],
+            Learner::KTails(1),
+        );
+        assert_eq!(automaton.states.len(), 1);
+        assert_eq!(automaton.transition_count(), 1);
+    }
+
+    /// Traces over four symbols from a fixed seed, negative when they contain symbol 3 twice
+    fn generated_traces(amount: usize) -> Vec<(Vec<usize>, u8)> {
+        let mut seed = 12345u64;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % bound) as usize
+        };
+        (0..amount)
+            .map(|_| {
+                let trace = (0..next(12) + 1).map(|_| next(4)).collect::<Vec<usize>>();
+                let label = (trace.iter().filter(|symbol| **symbol == 3).count() < 2) as u8;
+                (trace, label)
+            })
+            .collect()
+    }
+
+    fn alphabet() -> Vec<String> {
+        ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn trial_merge_is_undone() {
+        let mut automaton = Automaton::prefix_tree(alphabet(), &generated_traces(200));
+        let before = format!("{:?}", automaton.states);
+        let blue = automaton.states[0].transitions[&1].target;
+        let mut undo = Undo::new();
+        assert!(automaton.merge(0, blue, (0, 1), &mut undo).is_some());
+        assert_ne!(format!("{:?}", automaton.states), before);
+        automaton.undo(undo);
+        assert_eq!(format!("{:?}", automaton.states), before);
+    }
+
+    #[test]
+    fn learners_keep_the_sample_on_a_large_prefix_tree() {
+        // Large enough that copying the automaton for every trial merge takes minutes
+        let traces = generated_traces(600);
+        let prefix_tree = Automaton::prefix_tree(alphabet(), &traces);
+        assert!(prefix_tree.states.len() > 2000);
+
+        for learner in [Learner::KTails(2), Learner::Edsm(1)] {
+            let mut automaton = prefix_tree.clone();
+            automaton.learn(learner);
+            assert!(automaton.states.len() < prefix_tree.states.len());
+            for (trace, label) in traces.iter() {
+                let state = trace.iter().fold(0, |state, symbol| {
+                    automaton.states[state].transitions[symbol].target
+                });
+                if *label == 1 {
+                    assert!(automaton.states[state].accepting > 0);
+                } else {
+                    assert!(automaton.states[state].rejecting > 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_learner() {
+        assert_eq!("ktails".parse(), Ok(Learner::KTails(2)));
+        assert_eq!("ktails:3".parse(), Ok(Learner::KTails(3)));
+        assert_eq!("edsm".parse(), Ok(Learner::Edsm(1)));
+        assert_eq!("edsm:5".parse(), Ok(Learner::Edsm(5)));
+        assert!("edsm:x".parse::<Learner>().is_err());
+        assert!("rpni".parse::<Learner>().is_err());
+    }
+}
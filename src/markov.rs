@@ -0,0 +1,94 @@
+use crate::map_log;
+use crate::round::Round;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+// Synthetic labels before the first and after the last event of a round
+static START: &str = "START";
+static END: &str = "END";
+
+/// Counts of consecutive labels within rounds, a first order Markov chain over the labels
+#[derive(Debug, Default)]
+pub struct TransitionMatrix {
+    // from -> to -> count, ordered so the output is stable
+    counts: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl TransitionMatrix {
+    pub fn new() -> TransitionMatrix {
+        TransitionMatrix::default()
+    }
+
+    /// Count the transitions of every round, including the ones from START and to END
+    pub fn add_rounds(&mut self, all_log_sequence: &[Round], log_list: &[String]) {
+        for item in all_log_sequence.iter() {
+            let mut previous = String::from(START);
+            for event in item.events.iter() {
+                let label = map_log(&event.log_id, log_list, false);
+                self.add(&previous, &label);
+                previous = label;
+            }
+            self.add(&previous, END);
+        }
+    }
+
+    fn add(&mut self, from: &str, to: &str) {
+        *self
+            .counts
+            .entry(from.to_owned())
+            .or_default()
+            .entry(to.to_owned())
+            .or_insert(0) += 1;
+    }
+
+    /// Transitions with their count and the probability of taking them from their source label
+    fn transitions(&self) -> Vec<(&str, &str, u64, f64)> {
+        let mut transitions = Vec::new();
+        for (from, targets) in self.counts.iter() {
+            let total = targets.values().sum::<u64>();
+            for (to, count) in targets.iter() {
+                transitions.push((
+                    from.as_str(),
+                    to.as_str(),
+                    *count,
+                    *count as f64 / total as f64,
+                ));
+            }
+        }
+
+        transitions
+    }
+
+    /// Write the .markov.csv and .markov.dot files
+    pub fn write(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let transitions = self.transitions();
+
+        let mut csv_file = BufWriter::new(File::create(filename.to_owned() + ".markov.csv")?);
+        writeln!(csv_file, "from,to,count,probability")?;
+        for (from, to, count, probability) in transitions.iter() {
+            writeln!(csv_file, "{},{},{},{:.6}", from, to, count, probability)?;
+        }
+
+        let mut dot_file = BufWriter::new(File::create(filename.to_owned() + ".markov.dot")?);
+        writeln!(dot_file, "digraph markov {{")?;
+        writeln!(dot_file, "    rankdir=LR;")?;
+        writeln!(dot_file, "    \"{}\" [shape=box];", START)?;
+        writeln!(dot_file, "    \"{}\" [shape=box];", END)?;
+        for (from, to, count, probability) in transitions.iter() {
+            writeln!(
+                dot_file,
+                "    \"{}\" -> \"{}\" [label=\"{:.3}\" weight={} penwidth={:.2}];",
+                from,
+                to,
+                probability,
+                count,
+                1.0 + 4.0 * probability
+            )?;
+        }
+        writeln!(dot_file, "}}")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use crate::config::Config;
+use crate::pipeline::Pipeline;
+use crate::round::{format_timestamp, Round};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::str::FromStr;
+
+// Padding before the first label of a round and the label closing it
+static START: &str = "START";
+static END: &str = "END";
+
+/// Measure the rounds are ranked by, most surprising first
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Ranking {
+    // Lowest probability of the entire round, favours long rounds
+    LogLikelihood,
+    // Lowest probability per label, independent of the length of the round
+    #[default]
+    Perplexity,
+}
+
+impl FromStr for Ranking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Ranking, String> {
+        match s.trim() {
+            "likelihood" => Ok(Ranking::LogLikelihood),
+            "perplexity" => Ok(Ranking::Perplexity),
+            _ => Err(format!(
+                "unknown ranking \"{}\", expected likelihood or perplexity",
+                s
+            )),
+        }
+    }
+}
+
+/// Label n-grams of rounds, probabilities use add-one smoothing so unseen labels and
+/// continuations keep a small probability
+#[derive(Debug, Default)]
+pub struct NGramModel {
+    n: usize,
+    // Context of n - 1 labels -> label -> count
+    counts: HashMap<Vec<String>, HashMap<String, u64>>,
+    // Amount of n-grams per context
+    totals: HashMap<Vec<String>, u64>,
+    vocabulary: HashSet<String>,
+}
+
+impl NGramModel {
+    pub fn new(n: usize) -> NGramModel {
+        let mut vocabulary = HashSet::new();
+        vocabulary.insert(END.to_owned());
+        NGramModel {
+            n: n.max(1),
+            vocabulary,
+            ..NGramModel::default()
+        }
+    }
+
+    /// Labels of a round padded with START and closed with END, paired with their context
+    fn ngrams(&self, labels: &[String]) -> Vec<(Vec<String>, String)> {
+        let mut padded = vec![START.to_owned(); self.n - 1];
+        padded.extend(labels.iter().cloned());
+        padded.push(END.to_owned());
+
+        padded
+            .windows(self.n)
+            .map(|window| (window[..self.n - 1].to_vec(), window[self.n - 1].clone()))
+            .collect()
+    }
+
+    pub fn train(&mut self, labels: &[String]) {
+        for (context, label) in self.ngrams(labels) {
+            self.vocabulary.insert(label.clone());
+            *self.totals.entry(context.clone()).or_insert(0) += 1;
+            *self
+                .counts
+                .entry(context)
+                .or_default()
+                .entry(label)
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Natural log of the probability of the round, including its END
+    pub fn log_likelihood(&self, labels: &[String]) -> f64 {
+        // One extra symbol for labels never seen in the reference logs
+        let vocabulary = (self.vocabulary.len() + 1) as f64;
+        self.ngrams(labels)
+            .iter()
+            .map(|(context, label)| {
+                let count = self
+                    .counts
+                    .get(context)
+                    .and_then(|labels| labels.get(label))
+                    .cloned()
+                    .unwrap_or(0);
+                let total = self.totals.get(context).cloned().unwrap_or(0);
+                ((count + 1) as f64 / (total as f64 + vocabulary)).ln()
+            })
+            .sum()
+    }
+}
+
+/// Train an n-gram model on the `reference` logs and rank the rounds of the log by how surprising
+/// they are, the ranking is written to `.scores`
+pub fn score(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut model = NGramModel::new(config.ngram);
+    for reference in config.reference.iter() {
+        // The ranges and batches select rounds of the scored log, the model learns from all of the
+        // reference log
+        let mut reference_config = config.clone();
+        reference_config.filename = reference.clone();
+        reference_config.amount_batches = -1;
+        reference_config.time_range = None;
+        reference_config.ledger_range = None;
+        reference_config.round_range = None;
+        reference_config.mode = None;
+        reference_config.synced = None;
+
+        let mut pipeline = Pipeline::new();
+        pipeline.run(&reference_config, |batch, log_list| {
+            for item in batch.rounds.iter() {
+                model.train(&item.labels(log_list));
+            }
+            Ok(())
+        })?;
+    }
+
+    // (round, log-likelihood, perplexity)
+    let mut scores = Vec::<(Round, f64, f64)>::new();
+    let mut pipeline = Pipeline::new();
+    pipeline.run(config, |batch, log_list| {
+        for item in batch.rounds.into_iter() {
+            let labels = item.labels(log_list);
+            let log_likelihood = model.log_likelihood(&labels);
+            let perplexity = (-log_likelihood / (labels.len() + 1) as f64).exp();
+            scores.push((item, log_likelihood, perplexity));
+        }
+        Ok(())
+    })?;
+
+    match config.ranking {
+        Ranking::LogLikelihood => scores.sort_by(|a, b| a.1.total_cmp(&b.1)),
+        Ranking::Perplexity => scores.sort_by(|a, b| b.2.total_cmp(&a.2)),
+    }
+
+    let mut scores_file = BufWriter::new(File::create(format!("{}.scores", config.filename))?);
+    writeln!(
+        scores_file,
+        "rank round start end events log-likelihood perplexity"
+    )?;
+    for (rank, (item, log_likelihood, perplexity)) in scores.iter().enumerate() {
+        let line = format!(
+            "{} {} {} {} {} {:.4} {:.4}",
+            rank + 1,
+            item.index,
+            format_timestamp(&item.start).unwrap_or_else(|| String::from("-")),
+            format_timestamp(&item.end).unwrap_or_else(|| String::from("-")),
+            item.events.len(),
+            log_likelihood,
+            perplexity
+        );
+        writeln!(scores_file, "{}", line)?;
+        if rank < config.top {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
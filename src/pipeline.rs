@@ -0,0 +1,590 @@
+use crate::batching::Batching;
+use crate::checkpoint::{checkpoint_filename, settings, Checkpoint};
+use crate::classify::{classify_round, round_status};
+use crate::compress::CompressionStats;
+use crate::config::Config;
+use crate::filter::{before_range, ledger_in_range, parse_ledger, past_range, starts_in_range};
+use crate::reader::{search_lines, split_lines, ChunkReader};
+use crate::round::{parse_round_attributes, parse_timestamp, Event, Mode, Round, Status};
+use crate::session::{is_banner, is_time_jump, Restart, Session, BACKWARD_TOLERANCE_SECONDS};
+use crate::vocabulary::Vocabulary;
+use crate::{clean_all_log_sequence, map_log, match_line, sanitize_message};
+use chrono::{NaiveDateTime, TimeDelta};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use regex::bytes::Regex;
+use std::fs;
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+static LOG_ENTERING_CONSENSUS: &str = "LedgerConsensus:NFO Entering consensus process";
+static LOG_STARTING: &str = "Application:NFO Starting";
+static PROGRESS_TEMPLATE: &str = "{wide_bar} {bytes}/{total_bytes} {bytes_per_sec} eta {eta} {msg}";
+
+/// Header fields of a log line, parsed by the worker threads
+struct Line {
+    timestamp: Option<NaiveDateTime>,
+    // True for the line starting a new round
+    boundary: bool,
+    // Mode and sync state announced by the line starting a round
+    attributes: (Option<Mode>, Option<bool>),
+    // Set for lines written by a starting node
+    restart: Option<Restart>,
+    // Sequence of the ledger named by the message, only parsed when ledgers are filtered or batched
+    ledger: Option<u64>,
+    // Only set for lines passing `match_line`
+    event: Option<LineEvent>,
+    // True when the line was too old for the time range, only its header is parsed then
+    skipped: bool,
+}
+
+/// Parts of a log line that become an event
+struct LineEvent {
+    partition: String,
+    severity: String,
+    // Original message, only kept when configured
+    raw: Option<String>,
+    sanitized: String,
+}
+
+/// Split a log line into its header fields and sanitize the message, None for lines without a
+/// header other than the banner of a starting node; with `skip_old` the lines before the time range
+/// keep only the fields that count rounds and sessions
+fn parse_line(re: &Regex, l: &[u8], config: &Config, skip_old: bool) -> Option<Line> {
+    let mtch = match re.captures(l) {
+        Some(mtch) => mtch,
+        None if is_banner(l) => {
+            return Some(Line {
+                timestamp: None,
+                boundary: false,
+                attributes: (None, None),
+                restart: Some(Restart::Banner),
+                ledger: None,
+                event: None,
+                skipped: false,
+            })
+        }
+        None => return None,
+    };
+    let msg = String::from_utf8_lossy(mtch.get(1).unwrap().as_bytes());
+    // Everything in front of the message is the timestamp
+    let timestamp = parse_timestamp(&String::from_utf8_lossy(
+        &l[mtch.get(0).unwrap().start()..mtch.get(1).unwrap().start()],
+    ));
+    let origin = String::from_utf8_lossy(mtch.get(2).unwrap().as_bytes());
+    let level = String::from_utf8_lossy(mtch.get(3).unwrap().as_bytes());
+    let skipped = skip_old && before_range(timestamp, config);
+
+    let event = if !skipped && match_line(&origin, &level) {
+        Some(LineEvent {
+            partition: origin.into_owned(),
+            severity: level.into_owned(),
+            raw: if config.raw_messages {
+                Some(msg.clone().into_owned())
+            } else {
+                None
+            },
+            sanitized: sanitize_message(&msg),
+        })
+    } else {
+        None
+    };
+
+    let boundary = msg.starts_with(LOG_ENTERING_CONSENSUS);
+    Some(Line {
+        timestamp,
+        boundary,
+        attributes: if boundary {
+            parse_round_attributes(&msg)
+        } else {
+            (None, None)
+        },
+        restart: if msg.starts_with(LOG_STARTING) {
+            Some(Restart::Startup)
+        } else {
+            None
+        },
+        ledger: if !skipped
+            && (config.ledger_range.is_some() || matches!(config.batching, Batching::Ledgers(_)))
+        {
+            parse_ledger(&msg)
+        } else {
+            None
+        },
+        event,
+        skipped,
+    })
+}
+
+/// Timestamp of a log line, for looking up a time in the log without parsing the entire line
+fn line_timestamp(re: &Regex, l: &[u8]) -> Option<NaiveDateTime> {
+    let mtch = re.captures(l)?;
+    parse_timestamp(&String::from_utf8_lossy(
+        &l[mtch.get(0).unwrap().start()..mtch.get(1).unwrap().start()],
+    ))
+}
+
+/// Labeled and compressed rounds, handed over once the batch is full or its window has passed
+pub struct Batch {
+    // Suffix for the output files of this batch (e.g.: rounds_000_019 or 2020-03-05T14:00_14:10)
+    pub name: String,
+    pub rounds: Vec<Round>,
+    // False for the rounds left at the end of the log that did not fill a batch
+    pub complete: bool,
+}
+
+/// Reads a log and segments it into consensus rounds, assigning ids to the sanitized messages
+#[derive(Default)]
+pub struct Pipeline {
+    // Sanitized messages and their log_id
+    pub vocabulary: Vocabulary,
+    // List index log_id -> count
+    pub log_counts: Vec<u64>,
+    // Events seen and removed by compressing the sequences
+    pub compression_stats: CompressionStats,
+    // Rounds labeled as rejected by the classification rules
+    pub negative_rounds: u64,
+    // Runs of the node found in the log, a new one starts at every restart
+    pub sessions: Vec<Session>,
+    // Byte of the log reading started at, only past 0 when a `time-range` was looked up by binary
+    // search, round and session numbers then count from there
+    pub start_offset: u64,
+    // Position in the log to continue from, set by `resume`
+    resumed: Option<Checkpoint>,
+    // Size of the blocks the log is read in, the default of the reader unless set
+    block_bytes: Option<usize>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Restore the state of an interrupted run from its checkpoint, returning the index of the first
+    /// round that was not handed over yet
+    pub fn resume(&mut self, config: &Config) -> Result<usize, Box<dyn std::error::Error>> {
+        let filename = checkpoint_filename(config);
+        if !Path::new(&filename).exists() {
+            return Err(format!("no checkpoint {} to resume from", filename).into());
+        }
+        let checkpoint = Checkpoint::read(&filename)?;
+        if checkpoint.settings != settings(config) {
+            return Err(format!("checkpoint {} was written with other settings", filename).into());
+        }
+
+        // Interned in the same order, so every template gets its original id
+        self.vocabulary = Vocabulary::default();
+        for template in checkpoint.templates.iter() {
+            self.vocabulary.intern(template);
+        }
+        self.log_counts = checkpoint.log_counts.clone();
+        self.compression_stats = checkpoint.compression_stats;
+        self.negative_rounds = checkpoint.negative_rounds;
+        self.sessions = checkpoint.sessions.clone();
+        self.start_offset = checkpoint.start_offset;
+
+        let first_round = match checkpoint.partial.first() {
+            Some(item) => item.index,
+            None => checkpoint.rounds as usize,
+        };
+        self.resumed = Some(checkpoint);
+        Ok(first_round)
+    }
+
+    /// Process the logfile, calling `handle_batch` for every finished batch and finally once for
+    /// the remaining rounds
+    pub fn run<F>(
+        &mut self,
+        config: &Config,
+        mut handle_batch: F,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnMut(Batch, &[String]) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        let rounds_per_batch = config.rounds_per_batch;
+        let amount_batches = config.amount_batches;
+        let mut file = File::open(&config.filename)?;
+        let length = file.metadata()?.len();
+        let bar = ProgressBar::new(length);
+        bar.set_style(ProgressStyle::default_bar().template(PROGRESS_TEMPLATE));
+
+        // Regex separating on spaces in the log line, first match is the entire line, 1 is the message, 2 is the origin, 3 is the level
+        let re = Regex::new(r".{11}\s.{18}\s((\w+):(\w+)\s.+)").unwrap();
+
+        let mut rounds = 0;
+        let mut batches = 0;
+        let mut all_log_sequence = Vec::<Round>::new();
+        // True while the lines belong to a round that is kept
+        let mut started = false;
+        // True once reading stopped at the end of a range, no round can follow the buffered ones
+        let mut past = false;
+        let mut offset = 0;
+        // Timestamp of the previous line, a jump means the node was restarted in between
+        let mut last_timestamp = None;
+        if let Some(checkpoint) = self.resumed.take() {
+            last_timestamp = checkpoint.last_timestamp;
+            rounds = checkpoint.rounds;
+            batches = checkpoint.batches;
+            all_log_sequence = checkpoint.partial;
+            started = checkpoint.started;
+            offset = checkpoint.offset;
+        } else if let (Some(from), None) = (
+            config.time_range.and_then(|range| range.from),
+            config.round_range,
+        ) {
+            // Rounds before the time range are not needed, unless they are counted for a round range;
+            // lines of the first kept round may go back by the tolerance of a restart
+            let from = from - TimeDelta::seconds(BACKWARD_TOLERANCE_SECONDS);
+            offset = search_lines(&mut file, length, |l| {
+                line_timestamp(&re, l).map(|timestamp| timestamp >= from)
+            })?;
+            self.start_offset = offset;
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        bar.set_position(offset);
+
+        // Lines are read as blocks of bytes, only the parts that are kept become strings
+        let mut reader = match self.block_bytes {
+            Some(block_bytes) => ChunkReader::with_block_bytes(file, block_bytes),
+            None => ChunkReader::new(file),
+        };
+        reader.offset = offset;
+        let checkpoint_interval = Duration::from_secs(config.checkpoint);
+        let max_gap = TimeDelta::seconds(config.max_gap as i64);
+        let mut last_checkpoint = Instant::now();
+
+        // Lines are parsed and sanitized in parallel, rounds are still built in order of the log
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(config.threads)
+            .build()?;
+
+        'chunks: while let Some(chunk) = reader.next_chunk()? {
+            let chunk = split_lines(chunk);
+            let lines = pool.install(|| {
+                chunk
+                    .par_iter()
+                    .map(|l| parse_line(&re, l, config, true))
+                    .collect::<Vec<Option<Line>>>()
+            });
+
+            // The lines before the time range are still read, so the rounds and sessions keep
+            // their index within the whole log
+            for (line, l) in lines.into_iter().zip(chunk.iter()) {
+                let line = match line {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let timestamp = line.timestamp;
+
+                let restart = line.restart.or(match (last_timestamp, timestamp) {
+                    (Some(previous), Some(timestamp))
+                        if is_time_jump(previous, timestamp, config.restart_gap) =>
+                    {
+                        Some(Restart::TimeJump)
+                    }
+                    _ => None,
+                });
+                if timestamp.is_some() {
+                    last_timestamp = timestamp;
+                }
+                if self.sessions.is_empty() {
+                    self.sessions.push(Session::new(0, timestamp, None));
+                }
+                let session = self.sessions.last_mut().unwrap();
+                if session.start.is_none() {
+                    session.start = timestamp;
+                }
+
+                // Only a session with rounds is ended, the startup lines of a node all belong to
+                // the same restart
+                if let (Some(reason), Some(_)) = (restart, session.first_round) {
+                    // The round is cut off, the lines up to the next round are startup noise
+                    if started {
+                        started = false;
+                        if self.close_round(&mut all_log_sequence, Some(Status::Restart), config) {
+                            past = true;
+                            break 'chunks;
+                        }
+                    }
+                    let index = self.sessions.len() as u32;
+                    self.sessions
+                        .push(Session::new(index, timestamp, Some(reason)));
+                }
+
+                if line.boundary {
+                    // The ledger of the previous round is known once it ends
+                    if started && self.close_round(&mut all_log_sequence, None, config) {
+                        started = false;
+                        past = true;
+                        break 'chunks;
+                    }
+
+                    if let Some(end) = config
+                        .batching
+                        .batch_end(&all_log_sequence, rounds_per_batch)
+                    {
+                        // The round that ended the window starts the next batch
+                        let rest = all_log_sequence.split_off(end);
+                        self.hand_over(all_log_sequence, true, config, &mut handle_batch)?;
+                        all_log_sequence = rest;
+
+                        batches += 1;
+                        if batches == amount_batches {
+                            all_log_sequence.clear();
+                            started = false;
+                            break 'chunks;
+                        }
+                    }
+
+                    let session = self.sessions.last_mut().unwrap();
+                    let mut round = Round::new(rounds as usize, session.index, timestamp);
+                    (round.mode, round.synced) = line.attributes;
+                    session.first_round.get_or_insert(round.index);
+                    rounds += 1;
+                    if past_range(&round, config) {
+                        started = false;
+                        past = true;
+                        break 'chunks;
+                    }
+                    // Lines of rounds outside the ranges are skipped entirely
+                    started = starts_in_range(&round, config);
+                    if started {
+                        all_log_sequence.push(round);
+                    }
+                }
+
+                if !started {
+                    continue;
+                }
+                // Timestamps drifted back into a kept round, so the message is needed after all
+                let line = if line.skipped {
+                    parse_line(&re, l, config, false).unwrap()
+                } else {
+                    line
+                };
+
+                // Any line up to the next round belongs to the current one
+                let current = all_log_sequence.last_mut().unwrap();
+                if let (Some(end), Some(timestamp)) = (current.end, timestamp) {
+                    if config.max_gap > 0 && timestamp - end > max_gap {
+                        current.status = Status::Gap;
+                    }
+                }
+                if timestamp.is_some() {
+                    current.end = timestamp;
+                }
+                if current.ledger.is_none() {
+                    current.ledger = line.ledger;
+                }
+                let event = match line.event {
+                    Some(event) => event,
+                    None => continue,
+                };
+
+                let msg_sanitized = event.sanitized;
+
+                // get the log id, adding the message when it is new
+                let (log_id, is_new) = self.vocabulary.intern(&msg_sanitized);
+                if is_new {
+                    // initialize log counts as zero
+                    self.log_counts.push(0);
+                }
+
+                // Skip log if no mapping is defined
+                if map_log(&log_id, &self.vocabulary, is_new).is_empty() {
+                    continue;
+                }
+
+                // increase the count
+                *(self.log_counts.get_mut(log_id as usize).unwrap()) += 1;
+
+                // append the id to the current sequence, if none found, add a new one
+                let log_index = all_log_sequence.len() - 1;
+                all_log_sequence
+                    .get_mut(log_index)
+                    .unwrap()
+                    .events
+                    .push(Event {
+                        log_id,
+                        count: 1,
+                        timestamp,
+                        partition: event.partition,
+                        severity: event.severity,
+                        raw: event.raw,
+                    });
+            }
+
+            bar.set_position(reader.offset);
+            bar.set_message(&format!("{} rounds", rounds));
+
+            // Blocks always end with a whole line, so the checkpoint continues at the next one
+            if config.checkpoint > 0 && last_checkpoint.elapsed() >= checkpoint_interval {
+                let checkpoint = Checkpoint {
+                    settings: settings(config),
+                    offset: reader.offset,
+                    rounds,
+                    batches,
+                    started,
+                    last_timestamp,
+                    start_offset: self.start_offset,
+                    sessions: self.sessions.clone(),
+                    templates: self.vocabulary.to_vec(),
+                    log_counts: self.log_counts.clone(),
+                    compression_stats: self.compression_stats,
+                    negative_rounds: self.negative_rounds,
+                    partial: all_log_sequence.clone(),
+                };
+                checkpoint.write(&checkpoint_filename(config))?;
+                last_checkpoint = Instant::now();
+            }
+        }
+
+        bar.finish();
+
+        // The last round ended with the log
+        if started {
+            self.close_round(&mut all_log_sequence, Some(Status::Eof), config);
+        }
+        // It may still close the window of the rounds before it, a batch of rounds is only full
+        // once the next round starts
+        if config.batching != Batching::Rounds {
+            if let Some(end) = config
+                .batching
+                .batch_end(&all_log_sequence, rounds_per_batch)
+            {
+                let rest = all_log_sequence.split_off(end);
+                self.hand_over(all_log_sequence, true, config, &mut handle_batch)?;
+                all_log_sequence = rest;
+            }
+        }
+
+        // Rounds that did not fill a batch, unless the range ended before the log did they are
+        // only used when the handler wants them
+        if !all_log_sequence.is_empty() {
+            self.hand_over(all_log_sequence, past, config, &mut handle_batch)?;
+        }
+
+        // The run finished, a later run starts from the beginning again
+        if config.checkpoint > 0 || config.resume {
+            let checkpoint = checkpoint_filename(config);
+            if Path::new(&checkpoint).exists() {
+                fs::remove_file(checkpoint)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Settle the status of the round that just ended, `end` is what cut it off if anything, and
+    /// drop it when it lies outside the ledger range or is incomplete while these are left out;
+    /// true when it lies beyond the configured ranges so reading can stop
+    fn close_round(
+        &self,
+        all_log_sequence: &mut Vec<Round>,
+        end: Option<Status>,
+        config: &Config,
+    ) -> bool {
+        let previous = all_log_sequence.last_mut().unwrap();
+        previous.status = round_status(previous, end, &self.vocabulary);
+        if past_range(previous, config) {
+            all_log_sequence.pop();
+            return true;
+        }
+        if !ledger_in_range(previous, config)
+            || (config.drop_incomplete && previous.status != Status::Complete)
+        {
+            all_log_sequence.pop();
+        }
+
+        false
+    }
+
+    /// Hand the rounds of a batch to `handle_batch`, named after the window or rounds they cover
+    fn hand_over<F>(
+        &mut self,
+        all_log_sequence: Vec<Round>,
+        complete: bool,
+        config: &Config,
+        handle_batch: &mut F,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnMut(Batch, &[String]) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        let batch = Batch {
+            name: config.batching.name(&all_log_sequence),
+            rounds: self.finish_batch(all_log_sequence, config),
+            complete,
+        };
+        handle_batch(batch, &self.vocabulary)
+    }
+
+    /// Label and compress the rounds of a batch before they are handed over
+    fn finish_batch(&mut self, all_log_sequence: Vec<Round>, config: &Config) -> Vec<Round> {
+        let mut all_log_sequence = all_log_sequence;
+        for item in all_log_sequence.iter_mut() {
+            item.label = classify_round(item, &config.negative, &self.vocabulary);
+            if item.label == 0 {
+                self.negative_rounds += 1;
+            }
+        }
+
+        clean_all_log_sequence(
+            all_log_sequence,
+            config.compression,
+            &self.vocabulary,
+            &mut self.compression_stats,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ROUNDS_LOG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/rounds.log");
+
+    /// Batches, templates and sessions of a run over the test log
+    fn run(threads: usize, block_bytes: Option<usize>) -> (Vec<String>, Vec<String>, String) {
+        let config = Config {
+            filename: String::from(ROUNDS_LOG),
+            rounds_per_batch: 4,
+            amount_batches: -1,
+            raw_messages: true,
+            threads,
+            ..Config::default()
+        };
+        let mut pipeline = Pipeline::new();
+        pipeline.block_bytes = block_bytes;
+        let mut batches = Vec::new();
+        pipeline
+            .run(&config, |batch, _| {
+                batches.push(format!(
+                    "{} {} {:?}",
+                    batch.name, batch.complete, batch.rounds
+                ));
+                Ok(())
+            })
+            .unwrap();
+        (
+            batches,
+            pipeline.vocabulary.to_vec(),
+            format!("{:?}", pipeline.sessions),
+        )
+    }
+
+    #[test]
+    fn parallel_parsing_matches_a_sequential_run() {
+        let sequential = run(1, None);
+        assert!(sequential.0.len() > 2);
+        assert!(sequential.2.contains("TimeJump"));
+
+        // Blocks far smaller than the log, lines cross them and the longest line fits none
+        for (threads, block_bytes) in [(4, None), (1, Some(1024)), (4, Some(1024)), (3, Some(777))]
+        {
+            assert_eq!(run(threads, block_bytes), sequential);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::config::Config;
+use crate::pipeline::Pipeline;
+use crate::round::format_timestamp;
+use std::str::FromStr;
+
+/// Labels a single element of a pattern accepts
+#[derive(Clone, Debug, PartialEq)]
+enum Symbol {
+    // `.`, any label
+    Any,
+    // `a|b`, one of the labels
+    Labels(Vec<String>),
+    // `!a|b`, any label except these
+    Not(Vec<String>),
+}
+
+impl Symbol {
+    fn accepts(&self, label: &str) -> bool {
+        match self {
+            Symbol::Any => true,
+            Symbol::Labels(labels) => labels.iter().any(|l| l == label),
+            Symbol::Not(labels) => labels.iter().all(|l| l != label),
+        }
+    }
+}
+
+/// Element of a pattern with the amount of consecutive labels it matches
+#[derive(Clone, Debug, PartialEq)]
+struct Element {
+    symbol: Symbol,
+    min: usize,
+    // None for no upper bound
+    max: Option<usize>,
+}
+
+/// Sequence of labels searched for within a round: elements are separated by spaces, `.` matches
+/// any label, `a|b` one of the labels and `!a|b` any label except these; a `?`, `*` or `+` suffix
+/// repeats the element at most once, any amount of times or at least once, `..` is short for `.*`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    elements: Vec<Element>,
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Pattern, String> {
+        let mut elements = Vec::<Element>::new();
+        for token in s.split_whitespace() {
+            if token == ".." {
+                elements.push(Element {
+                    symbol: Symbol::Any,
+                    min: 0,
+                    max: None,
+                });
+                continue;
+            }
+
+            let (token, min, max) = match token.chars().last() {
+                Some('?') => (&token[..token.len() - 1], 0, Some(1)),
+                Some('*') => (&token[..token.len() - 1], 0, None),
+                Some('+') => (&token[..token.len() - 1], 1, None),
+                _ => (token, 1, Some(1)),
+            };
+            let labels = |labels: &str| -> Result<Vec<String>, String> {
+                let labels = labels
+                    .split('|')
+                    .map(|label| label.to_owned())
+                    .collect::<Vec<String>>();
+                if labels.iter().any(|label| label.is_empty()) {
+                    return Err(format!("empty label in \"{}\"", s));
+                }
+                Ok(labels)
+            };
+            let symbol = match token {
+                "." => Symbol::Any,
+                _ => match token.strip_prefix('!') {
+                    Some(negated) => Symbol::Not(labels(negated)?),
+                    None => Symbol::Labels(labels(token)?),
+                },
+            };
+            elements.push(Element { symbol, min, max });
+        }
+
+        if elements.is_empty() {
+            return Err(String::from("empty pattern"));
+        }
+        Ok(Pattern { elements })
+    }
+}
+
+impl Pattern {
+    /// Positions of the labels matched by the elements naming labels, for the leftmost and
+    /// shortest match in the sequence
+    pub fn find(&self, labels: &[String]) -> Option<Vec<usize>> {
+        for start in 0..=labels.len() {
+            let mut matched = Vec::<usize>::new();
+            if self.match_at(0, start, labels, &mut matched) {
+                return Some(matched);
+            }
+        }
+
+        None
+    }
+
+    fn match_at(
+        &self,
+        element: usize,
+        position: usize,
+        labels: &[String],
+        matched: &mut Vec<usize>,
+    ) -> bool {
+        let current = match self.elements.get(element) {
+            Some(current) => current,
+            None => return true,
+        };
+
+        // Consume as few labels as possible, backtracking into longer repetitions
+        let mut taken = 0;
+        let length = matched.len();
+        loop {
+            if taken >= current.min && self.match_at(element + 1, position + taken, labels, matched)
+            {
+                return true;
+            }
+            if current.max == Some(taken) {
+                break;
+            }
+            match labels.get(position + taken) {
+                Some(label) if current.symbol.accepts(label) => {
+                    if current.symbol != Symbol::Any {
+                        matched.push(position + taken);
+                    }
+                    taken += 1;
+                }
+                _ => break,
+            }
+        }
+        matched.truncate(length);
+
+        false
+    }
+}
+
+/// Search the rounds for the configured `pattern`, printing every matching round with the raw log
+/// lines of the matched events
+pub fn query(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let pattern = match &config.pattern {
+        Some(pattern) => pattern,
+        None => return Ok(()),
+    };
+    // The matched lines are printed as they were logged
+    let mut config = config.clone();
+    config.raw_messages = true;
+
+    let mut rounds = 0;
+    let mut matches = 0;
+    let mut pipeline = Pipeline::new();
+    pipeline.run(&config, |batch, log_list| {
+        for item in batch.rounds.iter() {
+            rounds += 1;
+            let labels = item.labels(log_list);
+            let positions = match pattern.find(&labels) {
+                Some(positions) => positions,
+                None => continue,
+            };
+
+            matches += 1;
+            println!(
+                "round {} start {} end {}",
+                item.index,
+                format_timestamp(&item.start).unwrap_or_else(|| String::from("-")),
+                format_timestamp(&item.end).unwrap_or_else(|| String::from("-"))
+            );
+            for position in positions.iter() {
+                let event = &item.events[*position];
+                println!(
+                    "  {} {}",
+                    format_timestamp(&event.timestamp).unwrap_or_else(|| String::from("-")),
+                    event.raw.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        Ok(())
+    })?;
+    println!("{} of {} rounds match", matches, rounds);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, labels: &str) -> Option<Vec<usize>> {
+        let pattern = pattern.parse::<Pattern>().unwrap();
+        let labels = labels
+            .split_whitespace()
+            .map(|label| label.to_owned())
+            .collect::<Vec<String>>();
+        pattern.find(&labels)
+    }
+
+    #[test]
+    fn leftmost_shortest_match() {
+        assert_eq!(find("a b", "x a b a b"), Some(vec![1, 2]));
+        assert_eq!(find("a .. b", "a b a c b"), Some(vec![0, 1]));
+        assert_eq!(find("a b+ c", "a b b c"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(find("a b* c", "a c"), Some(vec![0, 1]));
+        assert_eq!(find("a b? c", "a b c"), Some(vec![0, 1, 2]));
+        assert_eq!(find("a b? c", "a b b c"), None);
+    }
+
+    #[test]
+    fn any_label_is_not_reported() {
+        assert_eq!(find(". b", "a b"), Some(vec![1]));
+        assert_eq!(find("a . c", "a x c"), Some(vec![0, 2]));
+    }
+
+    #[test]
+    fn alternatives_and_negation() {
+        assert_eq!(find("b|c", "a c b"), Some(vec![1]));
+        assert_eq!(find("!a|b", "a b c"), Some(vec![2]));
+        assert_eq!(find("a !b+ a", "a c d a"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(find("a !b+ a", "a b a"), None);
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert!("".parse::<Pattern>().is_err());
+        assert!("   ".parse::<Pattern>().is_err());
+        assert!("a||b".parse::<Pattern>().is_err());
+        assert!("!".parse::<Pattern>().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufReader, SeekFrom};
+
+// Bytes read at once, a block is extended when a single line does not fit
+static CHUNK_BYTES: usize = 8 << 20;
+// Bytes read at once while searching, only a few lines are looked at per position
+static PROBE_BYTES: usize = 64 << 10;
+
+/// Reads a file in large blocks of whole lines, the buffer is reused for every block
+pub struct ChunkReader<R> {
+    reader: R,
+    // Bytes read at once, CHUNK_BYTES unless given
+    block_bytes: usize,
+    buffer: Vec<u8>,
+    // Length of the block handed out last, the bytes after it belong to the next block
+    consumed: usize,
+    eof: bool,
+    // Bytes of the file handed out so far
+    pub offset: u64,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub fn new(reader: R) -> ChunkReader<R> {
+        ChunkReader::with_block_bytes(reader, CHUNK_BYTES)
+    }
+
+    pub fn with_block_bytes(reader: R, block_bytes: usize) -> ChunkReader<R> {
+        ChunkReader {
+            reader,
+            block_bytes,
+            buffer: Vec::with_capacity(block_bytes),
+            consumed: 0,
+            eof: false,
+            offset: 0,
+        }
+    }
+
+    /// Next block of whole lines, including their newlines, None at the end of the file
+    pub fn next_chunk(&mut self) -> io::Result<Option<&[u8]>> {
+        // Keep the start of the line cut off by the previous block
+        self.buffer.drain(..self.consumed);
+
+        let mut target = self.block_bytes;
+        self.consumed = loop {
+            while self.buffer.len() < target && !self.eof {
+                let filled = self.buffer.len();
+                self.buffer.resize(target, 0);
+                match self.reader.read(&mut self.buffer[filled..]) {
+                    Ok(read) => {
+                        self.buffer.truncate(filled + read);
+                        self.eof = read == 0;
+                    }
+                    Err(error) => {
+                        self.buffer.truncate(filled);
+                        if error.kind() != io::ErrorKind::Interrupted {
+                            return Err(error);
+                        }
+                    }
+                }
+            }
+
+            match self.buffer.iter().rposition(|byte| *byte == b'\n') {
+                Some(newline) => break newline + 1,
+                // The last line of the file does not need a newline
+                None if self.eof => break self.buffer.len(),
+                None => target *= 2,
+            }
+        };
+
+        if self.consumed == 0 {
+            return Ok(None);
+        }
+        self.offset += self.consumed as u64;
+        Ok(Some(&self.buffer[..self.consumed]))
+    }
+}
+
+/// Lines of a block without their line endings
+pub fn split_lines(chunk: &[u8]) -> Vec<&[u8]> {
+    chunk
+        .split(|byte| *byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect()
+}
+
+/// Offset of the first line for which `reached` is true, by binary search over the file; it has to
+/// stay true for every line after that one, lines for which it gives None are skipped
+pub fn search_lines<R, F>(reader: &mut R, length: u64, reached: F) -> io::Result<u64>
+where
+    R: Read + Seek,
+    F: Fn(&[u8]) -> Option<bool>,
+{
+    let mut low = 0;
+    let mut high = length;
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match probe_line(reader, middle, &reached)? {
+            Some((_, false)) => low = middle + 1,
+            _ => high = middle,
+        }
+    }
+
+    Ok(match probe_line(reader, low, &reached)? {
+        Some((start, _)) => start,
+        None => length,
+    })
+}
+
+/// Start and result of the first line starting at or after `offset` that `reached` decides on
+fn probe_line<R, F>(reader: &mut R, offset: u64, reached: &F) -> io::Result<Option<(u64, bool)>>
+where
+    R: Read + Seek,
+    F: Fn(&[u8]) -> Option<bool>,
+{
+    // Start one byte early, so a line starting exactly at the offset is not skipped
+    let mut start = offset.saturating_sub(1);
+    reader.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::with_capacity(PROBE_BYTES, reader);
+    let mut line = Vec::<u8>::new();
+    if offset > 0 {
+        start += reader.read_until(b'\n', &mut line)? as u64;
+    }
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let content = line.strip_suffix(b"\n").unwrap_or(&line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        if let Some(reached) = reached(content) {
+            return Ok(Some((start, reached)));
+        }
+        start += read as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Every block of the input read with the given block size, checking that together they are
+    /// the input and that only the last one may end without a newline
+    fn read_chunks(input: &[u8], block_bytes: usize) -> Vec<Vec<u8>> {
+        let mut reader = ChunkReader::with_block_bytes(Cursor::new(input), block_bytes);
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.next_chunk().unwrap() {
+            chunks.push(chunk.to_vec());
+        }
+        assert_eq!(reader.offset, input.len() as u64);
+        assert_eq!(chunks.concat(), input);
+        for chunk in chunks.iter().rev().skip(1) {
+            assert!(chunk.ends_with(b"\n"));
+        }
+        chunks
+    }
+
+    #[test]
+    fn line_crossing_a_block_moves_to_the_next_one() {
+        let chunks = read_chunks(b"first\nsecond\nthird\n", 8);
+        assert_eq!(
+            chunks,
+            vec![
+                b"first\n".to_vec(),
+                b"second\n".to_vec(),
+                b"third\n".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn line_longer_than_a_block_extends_it() {
+        let chunks = read_chunks(b"a\nlonger than a block\nb\n", 4);
+        assert_eq!(chunks[0], b"a\n");
+        assert!(chunks[1].starts_with(b"longer than a block\n"));
+    }
+
+    #[test]
+    fn last_line_without_newline_is_kept() {
+        for block_bytes in [4, 8, 64] {
+            let chunks = read_chunks(b"first\nlast", block_bytes);
+            assert_eq!(chunks.last().unwrap(), b"last");
+        }
+    }
+
+    #[test]
+    fn lines_lose_their_line_endings() {
+        assert_eq!(
+            split_lines(b"a\r\nb\nlast"),
+            vec![&b"a"[..], &b"b"[..], &b"last"[..]]
+        );
+    }
+}
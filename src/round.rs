@@ -0,0 +1,189 @@
+use crate::map_log;
+use chrono::{Duration, NaiveDateTime};
+use std::fmt;
+use std::str::FromStr;
+
+/// A single log event within a consensus round
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub log_id: u64,
+    // Number of consecutive occurrences this event stands for, only above 1 after run-length compression
+    pub count: u64,
+    pub timestamp: Option<NaiveDateTime>,
+    // Origin of the log line (e.g.: LedgerConsensus)
+    pub partition: String,
+    // Level of the log line (e.g.: DBG)
+    pub severity: String,
+    // Original message, only kept when configured
+    pub raw: Option<String>,
+}
+
+/// Whether a round was logged from start to end, and if not what cut it short
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    // Ended by the next round after a closing event
+    Complete,
+    // Ended by the next round without any closing event
+    Unclosed,
+    // Lines more than `max-gap` seconds apart
+    Gap,
+    // Cut off by a restart of the node
+    Restart,
+    // Cut off by the end of the log
+    Eof,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Complete => write!(f, "complete"),
+            Status::Unclosed => write!(f, "unclosed"),
+            Status::Gap => write!(f, "gap"),
+            Status::Restart => write!(f, "restart"),
+            Status::Eof => write!(f, "eof"),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Status, String> {
+        match s {
+            "complete" => Ok(Status::Complete),
+            "unclosed" => Ok(Status::Unclosed),
+            "gap" => Ok(Status::Gap),
+            "restart" => Ok(Status::Restart),
+            "eof" => Ok(Status::Eof),
+            _ => Err(format!("unknown status \"{}\"", s)),
+        }
+    }
+}
+
+/// Role of the node in a round, announced by the line starting it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    // Sending validations for the ledgers it builds
+    Validating,
+    // Following consensus without validating
+    Watching,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Validating => write!(f, "validating"),
+            Mode::Watching => write!(f, "watching"),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "validating" => Ok(Mode::Validating),
+            "watching" => Ok(Mode::Watching),
+            _ => Err(format!(
+                "unknown mode \"{}\", expected validating or watching",
+                s
+            )),
+        }
+    }
+}
+
+/// All events logged between two "Entering consensus process" lines
+#[derive(Clone, Debug)]
+pub struct Round {
+    // Position of the round in the log, starting at 0
+    pub index: usize,
+    pub events: Vec<Event>,
+    // Abbadingo label, 1 for accepted rounds and 0 for rejected ones
+    pub label: u8,
+    // Timestamp of the "Entering consensus process" line
+    pub start: Option<NaiveDateTime>,
+    // Timestamp of the last log line before the next round starts
+    pub end: Option<NaiveDateTime>,
+    // Sequence of the first ledger named in the round, only parsed when ledgers are used
+    pub ledger: Option<u64>,
+    // Run of the node the round belongs to, counting the restarts before it
+    pub session: u32,
+    // Settled once the round ends, until then only a gap is recorded
+    pub status: Status,
+    // Announced by the "Entering consensus process" line, None when it does not say
+    pub mode: Option<Mode>,
+    pub synced: Option<bool>,
+}
+
+impl Round {
+    pub fn new(index: usize, session: u32, start: Option<NaiveDateTime>) -> Round {
+        Round {
+            index,
+            events: Vec::new(),
+            label: 1,
+            start,
+            end: start,
+            ledger: None,
+            session,
+            status: Status::Complete,
+            mode: None,
+            synced: None,
+        }
+    }
+
+    /// Name of the mode and sync state of the round (e.g.: validating_synced or watching_unsynced),
+    /// `unknown` for what the log did not announce
+    pub fn group(&self) -> String {
+        let mode = self
+            .mode
+            .map_or_else(|| String::from("unknown"), |mode| mode.to_string());
+        let synced = match self.synced {
+            Some(true) => "synced",
+            Some(false) => "unsynced",
+            None => "unknown",
+        };
+        format!("{}_{}", mode, synced)
+    }
+
+    /// Labels of the events of the round
+    pub fn labels(&self, log_list: &[String]) -> Vec<String> {
+        self.events
+            .iter()
+            .map(|event| map_log(&event.log_id, log_list, false))
+            .collect()
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => Some(end - start),
+            _ => None,
+        }
+    }
+}
+
+/// Format a timestamp as ISO 8601 (e.g.: 2020-03-05T09:39:43.524838466)
+pub fn format_timestamp(timestamp: &Option<NaiveDateTime>) -> Option<String> {
+    timestamp.map(|timestamp| timestamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+}
+
+/// Parse the timestamp at the start of a log line (e.g.: 2020-Mar-05 09:39:43.524838466)
+pub fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp.trim(), "%Y-%b-%d %H:%M:%S%.f").ok()
+}
+
+/// Mode and sync state announced by the line starting a round (e.g.: Entering consensus process,
+/// validating, synced=yes)
+pub fn parse_round_attributes(msg: &str) -> (Option<Mode>, Option<bool>) {
+    let mut mode = None;
+    let mut synced = None;
+    for part in msg.split(',').skip(1) {
+        match part.trim() {
+            "synced=yes" => synced = Some(true),
+            "synced=no" => synced = Some(false),
+            part => mode = mode.or(part.parse().ok()),
+        }
+    }
+
+    (mode, synced)
+}
@@ -0,0 +1,114 @@
+use crate::round::format_timestamp;
+use chrono::{Duration, NaiveDateTime};
+use regex::bytes::Regex;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+// Timestamps may go back this far without a restart, log lines of different threads interleave
+pub static BACKWARD_TOLERANCE_SECONDS: i64 = 1;
+
+lazy_static! {
+    // Version banner written by the node when it starts, without the usual line header
+    static ref BANNER: Regex = Regex::new(r"^\s*(?:rippled|xrpld)\b.*\bversion\b").unwrap();
+}
+
+/// Sign in the log that the node was restarted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Restart {
+    // Startup line of the application
+    Startup,
+    // Timestamps going back, or forward by more than `restart-gap`
+    TimeJump,
+    // Version banner of a new process
+    Banner,
+}
+
+impl fmt::Display for Restart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Restart::Startup => write!(f, "startup"),
+            Restart::TimeJump => write!(f, "time-jump"),
+            Restart::Banner => write!(f, "banner"),
+        }
+    }
+}
+
+impl Restart {
+    pub fn from_name(name: &str) -> Option<Restart> {
+        match name {
+            "startup" => Some(Restart::Startup),
+            "time-jump" => Some(Restart::TimeJump),
+            "banner" => Some(Restart::Banner),
+            _ => None,
+        }
+    }
+}
+
+/// Part of the log written by a single run of the node
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub index: u32,
+    // Timestamp of the first line of the session
+    pub start: Option<NaiveDateTime>,
+    // None for the session the log starts with
+    pub reason: Option<Restart>,
+    pub first_round: Option<usize>,
+}
+
+impl Session {
+    pub fn new(index: u32, start: Option<NaiveDateTime>, reason: Option<Restart>) -> Session {
+        Session {
+            index,
+            start,
+            reason,
+            first_round: None,
+        }
+    }
+}
+
+/// Whether a line without header is the banner of a starting node
+pub fn is_banner(l: &[u8]) -> bool {
+    BANNER.is_match(l)
+}
+
+/// Whether the time between two consecutive lines means the node was restarted in between, a gap
+/// of 0 seconds only detects timestamps going back
+pub fn is_time_jump(previous: NaiveDateTime, timestamp: NaiveDateTime, gap: u64) -> bool {
+    let elapsed = timestamp - previous;
+    if elapsed < -Duration::seconds(BACKWARD_TOLERANCE_SECONDS) {
+        return true;
+    }
+    gap > 0 && elapsed > Duration::seconds(gap as i64)
+}
+
+/// Write the .sessions file, the byte of the log reading started at followed by one line per
+/// session with its start, first round and what started it
+pub fn write_sessions(
+    filename: &str,
+    sessions: &[Session],
+    start_offset: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let sessions_file = File::create(filename.to_owned() + ".sessions")?;
+    let mut sessions_file = BufWriter::new(sessions_file);
+
+    writeln!(sessions_file, "start-offset {}", start_offset)?;
+
+    for session in sessions.iter() {
+        writeln!(
+            sessions_file,
+            "session {} start {} first-round {} reason {}",
+            session.index,
+            format_timestamp(&session.start).unwrap_or_else(|| String::from("-")),
+            session
+                .first_round
+                .map_or_else(|| String::from("-"), |index| index.to_string()),
+            session
+                .reason
+                .map_or_else(|| String::from("log"), |reason| reason.to_string())
+        )?;
+    }
+
+    Ok(())
+}
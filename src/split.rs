@@ -0,0 +1,219 @@
+use crate::config::Config;
+use crate::config::Format;
+use crate::round::Round;
+use crate::{write_mapping, write_rounds};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::str::FromStr;
+
+/// How the rounds of a dataset export are divided over the output files
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Split {
+    // Relative sizes of the train, validation and test sets
+    Ratios(f64, f64, f64),
+    // k folds, each fold is the test set once while the others form the train set
+    KFold(usize),
+}
+
+impl FromStr for Split {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Split, String> {
+        if let Some(k) = s.strip_prefix("kfold:") {
+            return match k.parse::<usize>() {
+                Ok(k) if k > 1 => Ok(Split::KFold(k)),
+                _ => Err(format!(
+                    "invalid fold count \"{}\", expected kfold:<k> with k > 1",
+                    s
+                )),
+            };
+        }
+
+        let ratios = s
+            .split(',')
+            .map(|ratio| ratio.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| format!("invalid ratios \"{}\"", s))?;
+        match ratios.as_slice() {
+            [train, validation, test]
+                if ratios.iter().all(|ratio| *ratio >= 0.0) && train + validation + test > 0.0 =>
+            {
+                Ok(Split::Ratios(*train, *validation, *test))
+            }
+            _ => Err(format!(
+                "invalid split \"{}\", expected <train>,<validation>,<test> ratios or kfold:<k>",
+                s
+            )),
+        }
+    }
+}
+
+impl Split {
+    /// Amount of rounds in every set: the train, validation and test set, or the test set of every
+    /// fold
+    fn set_lengths(&self, len: usize) -> Vec<usize> {
+        match *self {
+            Split::Ratios(train, validation, test) => {
+                let total = train + validation + test;
+                let train_len = (len as f64 * train / total).round() as usize;
+                let validation_len =
+                    ((len as f64 * validation / total).round() as usize).min(len - train_len);
+                vec![train_len, validation_len, len - train_len - validation_len]
+            }
+            // Every fold gets an equally sized chunk of the shuffled rounds, the first folds get the
+            // remainder
+            Split::KFold(k) => (0..k)
+                .map(|fold| len / k + if fold < len % k { 1 } else { 0 })
+                .collect(),
+        }
+    }
+}
+
+/// SplitMix64, a small generator so the same seed gives the same split on every platform and version
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Fisher-Yates shuffle of the positions 0..len
+fn shuffled_positions(len: usize, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64(seed);
+    let mut positions = (0..len).collect::<Vec<usize>>();
+    for i in (1..len).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        positions.swap(i, j);
+    }
+
+    positions
+}
+
+/// Shuffle the rounds and write the train/validation/test files (or one pair per fold) in every
+/// configured format, sharing a single mapping, together with a manifest recording which round
+/// went where
+pub fn write_split(
+    filename: &str,
+    rounds: &[Round],
+    log_list: &[String],
+    split: Split,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let positions = shuffled_positions(rounds.len(), config.seed);
+    let select = |positions: &[usize]| {
+        positions
+            .iter()
+            .map(|position| rounds[*position].clone())
+            .collect::<Vec<Round>>()
+    };
+
+    let manifest_file = File::create(filename.to_owned() + ".manifest")?;
+    let mut manifest_file = BufWriter::new(manifest_file);
+
+    let lengths = split.set_lengths(rounds.len());
+    match split {
+        Split::Ratios(..) => {
+            let (train_len, validation_len) = (lengths[0], lengths[1]);
+            let sets = [
+                ("train", &positions[..train_len]),
+                (
+                    "validation",
+                    &positions[train_len..train_len + validation_len],
+                ),
+                ("test", &positions[train_len + validation_len..]),
+            ];
+            for (name, set) in sets.iter() {
+                write_rounds(
+                    &format!("{}_{}", filename, name),
+                    &select(set),
+                    log_list,
+                    config,
+                )?;
+                for position in set.iter() {
+                    writeln!(manifest_file, "{} {}", rounds[*position].index, name)?;
+                }
+            }
+        }
+        Split::KFold(_) => {
+            let mut start = 0;
+            for (fold, len) in lengths.into_iter().enumerate() {
+                let test = &positions[start..start + len];
+                let train = [&positions[..start], &positions[start + len..]].concat();
+                start += len;
+
+                write_rounds(
+                    &format!("{}_fold_{}_train", filename, fold),
+                    &select(&train),
+                    log_list,
+                    config,
+                )?;
+                write_rounds(
+                    &format!("{}_fold_{}_test", filename, fold),
+                    &select(test),
+                    log_list,
+                    config,
+                )?;
+                for position in test.iter() {
+                    writeln!(manifest_file, "{} fold_{}", rounds[*position].index, fold)?;
+                }
+            }
+        }
+    }
+
+    if config.formats.contains(&Format::Abbadingo) {
+        let mapping_file = File::create(filename.to_owned() + ".mapping")?;
+        write_mapping(mapping_file, log_list)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_split() {
+        assert_eq!("0.8,0.1,0.1".parse(), Ok(Split::Ratios(0.8, 0.1, 0.1)));
+        assert_eq!("8, 1, 1".parse(), Ok(Split::Ratios(8.0, 1.0, 1.0)));
+        assert_eq!("kfold:5".parse(), Ok(Split::KFold(5)));
+        assert!("kfold:1".parse::<Split>().is_err());
+        assert!("0.8,0.2".parse::<Split>().is_err());
+        assert!("0,0,0".parse::<Split>().is_err());
+        assert!("-1,1,1".parse::<Split>().is_err());
+    }
+
+    #[test]
+    fn ratio_lengths_cover_all_rounds() {
+        assert_eq!(
+            Split::Ratios(0.8, 0.1, 0.1).set_lengths(100),
+            vec![80, 10, 10]
+        );
+        assert_eq!(Split::Ratios(8.0, 1.0, 1.0).set_lengths(7), vec![6, 1, 0]);
+        assert_eq!(Split::Ratios(0.5, 0.5, 0.0).set_lengths(3), vec![2, 1, 0]);
+        assert_eq!(Split::Ratios(1.0, 1.0, 1.0).set_lengths(0), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn first_folds_get_the_remainder() {
+        assert_eq!(Split::KFold(3).set_lengths(10), vec![4, 3, 3]);
+        assert_eq!(Split::KFold(4).set_lengths(2), vec![1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn shuffle_is_a_stable_permutation() {
+        let positions = shuffled_positions(50, 7);
+        assert_eq!(positions, shuffled_positions(50, 7));
+        assert_ne!(positions, shuffled_positions(50, 8));
+
+        let mut sorted = positions.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..50).collect::<Vec<usize>>());
+    }
+}
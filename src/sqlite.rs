@@ -0,0 +1,189 @@
+use crate::config::Config;
+use crate::map_log;
+use crate::round::{format_timestamp, Round};
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::Path;
+
+static SCHEMA: &str = "
+    CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        logfile TEXT NOT NULL,
+        compression TEXT NOT NULL
+    );
+    CREATE TABLE nodes (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE labels (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE templates (
+        id INTEGER PRIMARY KEY,
+        template TEXT NOT NULL,
+        label_id INTEGER REFERENCES labels(id)
+    );
+    CREATE TABLE rounds (
+        id INTEGER PRIMARY KEY,
+        run_id INTEGER NOT NULL REFERENCES runs(id),
+        node_id INTEGER NOT NULL REFERENCES nodes(id),
+        round_index INTEGER NOT NULL,
+        session INTEGER NOT NULL,
+        start TEXT,
+        end TEXT,
+        label INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        mode TEXT,
+        synced INTEGER
+    );
+    CREATE TABLE events (
+        id INTEGER PRIMARY KEY,
+        round_id INTEGER NOT NULL REFERENCES rounds(id),
+        position INTEGER NOT NULL,
+        timestamp TEXT,
+        partition TEXT NOT NULL,
+        severity TEXT NOT NULL,
+        template_id INTEGER NOT NULL REFERENCES templates(id),
+        count INTEGER NOT NULL,
+        raw TEXT
+    );
+    CREATE INDEX events_round ON events(round_id);
+    CREATE INDEX events_template ON events(template_id);
+";
+
+/// SQLite database holding all rounds of a run, batches are added as they are finished
+pub struct Database {
+    connection: Connection,
+    run_id: i64,
+    node_id: i64,
+    // Templates up to this id are already stored
+    templates_written: usize,
+}
+
+impl Database {
+    /// Create a new database, replacing the one of a previous run
+    pub fn create(path: &str, config: &Config) -> Result<Database, Box<dyn std::error::Error>> {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        connection.execute(
+            "INSERT INTO runs (logfile, compression) VALUES (?1, ?2)",
+            params![config.filename, config.compression.to_string()],
+        )?;
+        let run_id = connection.last_insert_rowid();
+        connection.execute("INSERT INTO nodes (name) VALUES (?1)", params![config.node])?;
+        let node_id = connection.last_insert_rowid();
+
+        Ok(Database {
+            connection,
+            run_id,
+            node_id,
+            templates_written: 0,
+        })
+    }
+
+    /// Open the database of an interrupted run, dropping the rounds from `first_round` on since
+    /// they are written again
+    pub fn resume(path: &str, first_round: usize) -> Result<Database, Box<dyn std::error::Error>> {
+        let connection = Connection::open(path)?;
+        let run_id = connection.query_row("SELECT MAX(id) FROM runs", [], |row| row.get(0))?;
+        let node_id = connection.query_row("SELECT MAX(id) FROM nodes", [], |row| row.get(0))?;
+        connection.execute(
+            "DELETE FROM events WHERE round_id IN (SELECT id FROM rounds WHERE round_index >= ?1)",
+            params![first_round as i64],
+        )?;
+        connection.execute(
+            "DELETE FROM rounds WHERE round_index >= ?1",
+            params![first_round as i64],
+        )?;
+        let templates_written: i64 =
+            connection.query_row("SELECT COUNT(*) FROM templates", [], |row| row.get(0))?;
+
+        Ok(Database {
+            connection,
+            run_id,
+            node_id,
+            templates_written: templates_written as usize,
+        })
+    }
+
+    /// Store the rounds of a batch, together with the templates discovered since the previous one
+    pub fn insert_batch(
+        &mut self,
+        all_log_sequence: &[Round],
+        log_list: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+
+        for (id, template) in log_list.iter().enumerate().skip(self.templates_written) {
+            // Templates without mapping are never part of a round and have no label
+            let label = map_log(&(id as u64), log_list, false);
+            let label_id: Option<i64> = if label.is_empty() {
+                None
+            } else {
+                transaction.execute(
+                    "INSERT OR IGNORE INTO labels (name) VALUES (?1)",
+                    params![label],
+                )?;
+                Some(transaction.query_row(
+                    "SELECT id FROM labels WHERE name = ?1",
+                    params![label],
+                    |row| row.get(0),
+                )?)
+            };
+            transaction.execute(
+                "INSERT INTO templates (id, template, label_id) VALUES (?1, ?2, ?3)",
+                params![id as i64, template, label_id],
+            )?;
+        }
+        // A resumed run may hand over fewer templates than a batch written before the interruption
+        self.templates_written = self.templates_written.max(log_list.len());
+
+        {
+            let mut insert_round = transaction.prepare(
+                "INSERT INTO rounds (run_id, node_id, round_index, session, start, end, label, status, mode, synced)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            let mut insert_event = transaction.prepare(
+                "INSERT INTO events (round_id, position, timestamp, partition, severity, template_id, count, raw)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+
+            for item in all_log_sequence.iter() {
+                let round_id = insert_round.insert(params![
+                    self.run_id,
+                    self.node_id,
+                    item.index as i64,
+                    item.session,
+                    format_timestamp(&item.start),
+                    format_timestamp(&item.end),
+                    item.label,
+                    item.status.to_string(),
+                    item.mode.map(|mode| mode.to_string()),
+                    item.synced,
+                ])?;
+
+                for (position, event) in item.events.iter().enumerate() {
+                    insert_event.execute(params![
+                        round_id,
+                        position as i64,
+                        format_timestamp(&event.timestamp),
+                        event.partition,
+                        event.severity,
+                        event.log_id as i64,
+                        event.count as i64,
+                        event.raw,
+                    ])?;
+                }
+            }
+        }
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
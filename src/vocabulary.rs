@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// Sanitized templates with dense ids in order of first appearance, they can be looked up by id by
+/// indexing the vocabulary as a slice, or by text with `id` and `intern`
+#[derive(Debug, Default)]
+pub struct Vocabulary {
+    // List index log_id -> template
+    templates: Vec<String>,
+    // Template -> log_id
+    ids: HashMap<Box<str>, u32>,
+}
+
+impl Vocabulary {
+    /// Id of the template, if it was seen before
+    pub fn id(&self, template: &str) -> Option<u64> {
+        self.ids.get(template).map(|id| *id as u64)
+    }
+
+    /// Id of the template, adding it when it is new, the flag is true for new templates
+    pub fn intern(&mut self, template: &str) -> (u64, bool) {
+        if let Some(id) = self.id(template) {
+            return (id, false);
+        }
+
+        let id = self.templates.len() as u32;
+        self.templates.push(template.to_owned());
+        self.ids.insert(template.into(), id);
+        (id as u64, true)
+    }
+}
+
+/// The templates in order of their id, for writers that take the plain list
+impl Deref for Vocabulary {
+    type Target = [String];
+
+    fn deref(&self) -> &[String] {
+        &self.templates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_gives_dense_ids_in_order_of_appearance() {
+        let mut vocabulary = Vocabulary::default();
+        assert_eq!(vocabulary.intern("Starting"), (0, true));
+        assert_eq!(vocabulary.intern("Started"), (1, true));
+        assert_eq!(vocabulary.intern("Starting"), (0, false));
+        assert_eq!(vocabulary.intern(""), (2, true));
+        assert_eq!(vocabulary.intern("Started"), (1, false));
+        assert_eq!(vocabulary.len(), 3);
+        assert_eq!(vocabulary.to_vec(), vec!["Starting", "Started", ""]);
+    }
+
+    #[test]
+    fn ids_and_templates_round_trip() {
+        let mut vocabulary = Vocabulary::default();
+        let templates = (0..1000)
+            .map(|i| format!("Got tx #{}", i % 300))
+            .collect::<Vec<String>>();
+        for template in templates.iter() {
+            let (id, _) = vocabulary.intern(template);
+            assert_eq!(vocabulary[id as usize], *template);
+        }
+        assert_eq!(vocabulary.len(), 300);
+        for (id, template) in vocabulary.iter().enumerate() {
+            assert_eq!(vocabulary.id(template), Some(id as u64));
+        }
+        assert_eq!(vocabulary.id("Got tx #300"), None);
+    }
+}
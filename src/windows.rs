@@ -0,0 +1,129 @@
+use crate::config::Config;
+use crate::round::Round;
+use crate::write_mapping;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::str::FromStr;
+
+/// Which template ids a window may span
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WindowScope {
+    // Windows stay within a round, rounds with no more than `window` events give no rows
+    #[default]
+    Round,
+    // Windows slide over all events of the run, ignoring where rounds and batches start
+    Stream,
+}
+
+impl FromStr for WindowScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<WindowScope, String> {
+        match s.trim() {
+            "round" => Ok(WindowScope::Round),
+            "stream" => Ok(WindowScope::Stream),
+            _ => Err(format!(
+                "unknown window scope \"{}\", expected round or stream",
+                s
+            )),
+        }
+    }
+}
+
+/// Write a row with the round of the next event, the template ids of the window and the id of the
+/// next event
+fn write_row<'a, W, I>(out_file: &mut W, window: I, next: (usize, u64)) -> std::io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = &'a (usize, u64)>,
+{
+    let window = window
+        .map(|(_, log_id)| log_id.to_string())
+        .collect::<Vec<String>>();
+    writeln!(out_file, "{},{},{}", next.0, window.join(" "), next.1)
+}
+
+/// Write the next event prediction dataset of a batch for the round scope: a .windows file with
+/// the round, `window` template ids and the id following them per row, and the .vocab file of all
+/// template ids
+pub fn write_windows(
+    filename: &str,
+    all_log_sequence: &[Round],
+    log_list: &[String],
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let windows_file = File::create(filename.to_owned() + ".windows")?;
+    let mut windows_file = BufWriter::new(windows_file);
+    writeln!(windows_file, "round,window,next")?;
+
+    for item in all_log_sequence.iter() {
+        // (round index, template id) of the events
+        let events = item
+            .events
+            .iter()
+            .map(|event| (item.index, event.log_id))
+            .collect::<Vec<(usize, u64)>>();
+        for rows in events.windows(config.window + 1) {
+            let (window, next) = rows.split_at(config.window);
+            write_row(&mut windows_file, window.iter(), next[0])?;
+        }
+    }
+
+    let vocab_file = File::create(filename.to_owned() + ".vocab")?;
+    write_mapping(vocab_file, log_list)
+}
+
+/// Windows sliding over the events of all batches of the run for the stream scope, written to a
+/// single .windows file as the batches come in
+pub struct WindowStream {
+    windows_file: BufWriter<File>,
+    // (round index, template id) of the last `window` events
+    history: VecDeque<(usize, u64)>,
+    window: usize,
+}
+
+impl WindowStream {
+    pub fn create(
+        filename: &str,
+        config: &Config,
+    ) -> Result<WindowStream, Box<dyn std::error::Error>> {
+        let windows_file = File::create(filename.to_owned() + ".windows")?;
+        let mut windows_file = BufWriter::new(windows_file);
+        writeln!(windows_file, "round,window,next")?;
+
+        Ok(WindowStream {
+            windows_file,
+            history: VecDeque::with_capacity(config.window + 1),
+            window: config.window,
+        })
+    }
+
+    /// Slide the window over the events of a batch, continuing from the previous batch
+    pub fn add_rounds(&mut self, all_log_sequence: &[Round]) -> std::io::Result<()> {
+        for item in all_log_sequence.iter() {
+            for event in item.events.iter() {
+                let next = (item.index, event.log_id);
+                if self.history.len() == self.window {
+                    write_row(&mut self.windows_file, self.history.iter(), next)?;
+                    self.history.pop_front();
+                }
+                self.history.push_back(next);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish the .windows file and write the .vocab file of all template ids
+    pub fn close(
+        mut self,
+        filename: &str,
+        log_list: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.windows_file.flush()?;
+        let vocab_file = File::create(filename.to_owned() + ".vocab")?;
+        write_mapping(vocab_file, log_list)
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::Config;
+use crate::map_log;
+use crate::round::Round;
+use chrono::NaiveDateTime;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+/// Escape a value for use in an XML attribute
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Format a timestamp as xs:dateTime, log timestamps are in UTC
+fn xes_timestamp(timestamp: &NaiveDateTime) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S%.3f+00:00").to_string()
+}
+
+/// Write the rounds as XES event log for process mining tools, every round is a trace and every
+/// event is named after its label
+pub fn write_xes(
+    filename: &str,
+    all_log_sequence: &[Round],
+    log_list: &[String],
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let xes_file = File::create(filename.to_owned() + ".xes")?;
+    let mut xes_file = BufWriter::new(xes_file);
+
+    writeln!(xes_file, r#"<?xml version="1.0" encoding="UTF-8" ?>"#)?;
+    writeln!(
+        xes_file,
+        r#"<log xes.version="1.0" xes.features="nested-attributes" xmlns="http://www.xes-standard.org/">"#
+    )?;
+    writeln!(
+        xes_file,
+        r#"  <extension name="Concept" prefix="concept" uri="http://www.xes-standard.org/concept.xesext"/>"#
+    )?;
+    writeln!(
+        xes_file,
+        r#"  <extension name="Time" prefix="time" uri="http://www.xes-standard.org/time.xesext"/>"#
+    )?;
+    writeln!(xes_file, r#"  <global scope="trace">"#)?;
+    writeln!(
+        xes_file,
+        r#"    <string key="concept:name" value="__INVALID__"/>"#
+    )?;
+    writeln!(xes_file, r#"  </global>"#)?;
+    writeln!(xes_file, r#"  <global scope="event">"#)?;
+    writeln!(
+        xes_file,
+        r#"    <string key="concept:name" value="__INVALID__"/>"#
+    )?;
+    writeln!(xes_file, r#"  </global>"#)?;
+    writeln!(
+        xes_file,
+        r#"  <classifier name="Activity" keys="concept:name"/>"#
+    )?;
+    writeln!(
+        xes_file,
+        r#"  <string key="concept:name" value="{}"/>"#,
+        escape(&config.node)
+    )?;
+
+    for item in all_log_sequence.iter() {
+        writeln!(xes_file, "  <trace>")?;
+        writeln!(
+            xes_file,
+            r#"    <string key="concept:name" value="round {}"/>"#,
+            item.index
+        )?;
+        writeln!(xes_file, r#"    <int key="label" value="{}"/>"#, item.label)?;
+        writeln!(
+            xes_file,
+            r#"    <int key="session" value="{}"/>"#,
+            item.session
+        )?;
+        writeln!(
+            xes_file,
+            r#"    <string key="status" value="{}"/>"#,
+            item.status
+        )?;
+        if let Some(mode) = item.mode {
+            writeln!(xes_file, r#"    <string key="mode" value="{}"/>"#, mode)?;
+        }
+        if let Some(synced) = item.synced {
+            writeln!(
+                xes_file,
+                r#"    <boolean key="synced" value="{}"/>"#,
+                synced
+            )?;
+        }
+
+        for event in item.events.iter() {
+            writeln!(xes_file, "    <event>")?;
+            writeln!(
+                xes_file,
+                r#"      <string key="concept:name" value="{}"/>"#,
+                escape(&map_log(&event.log_id, log_list, false))
+            )?;
+            if let Some(timestamp) = &event.timestamp {
+                writeln!(
+                    xes_file,
+                    r#"      <date key="time:timestamp" value="{}"/>"#,
+                    xes_timestamp(timestamp)
+                )?;
+            }
+            writeln!(
+                xes_file,
+                r#"      <int key="template_id" value="{}"/>"#,
+                event.log_id
+            )?;
+            writeln!(
+                xes_file,
+                r#"      <string key="template" value="{}"/>"#,
+                escape(&log_list[event.log_id as usize])
+            )?;
+            if event.count > 1 {
+                writeln!(
+                    xes_file,
+                    r#"      <int key="count" value="{}"/>"#,
+                    event.count
+                )?;
+            }
+            writeln!(xes_file, "    </event>")?;
+        }
+
+        writeln!(xes_file, "  </trace>")?;
+    }
+
+    writeln!(xes_file, "</log>")?;
+
+    Ok(())
+}